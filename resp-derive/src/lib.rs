@@ -0,0 +1,194 @@
+//! Derive macros for `resp-rs`, re-exported by it behind the `derive`
+//! feature.
+//!
+//! Only structs with named fields are supported. A field is looked up by
+//! its name unless renamed with `#[resp(rename = "...")]`.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+struct Field {
+    /// The identifier as written, e.g. `r#type`.
+    ident: String,
+    /// The key the field is stored under.
+    key: String,
+}
+
+struct Struct {
+    name: String,
+    fields: Vec<Field>,
+}
+
+/// Decodes a struct from a map reply or a flat `[key, value, ...]` array.
+/// Unknown keys are ignored, and a missing key is only accepted for
+/// `Option` fields.
+#[proc_macro_derive(FromResp, attributes(resp))]
+pub fn derive_from_resp(input: TokenStream) -> TokenStream {
+    let st = match parse_struct(input) {
+        Ok(st) => st,
+        Err(msg) => return compile_error(&msg),
+    };
+    let inits: String = st
+        .fields
+        .iter()
+        .map(|f| format!("{}: fields.take({:?})?,", f.ident, f.key))
+        .collect();
+    format!(
+        "impl ::resp_rs::FromResp for {name} {{
+            fn from_resp(value: ::resp_rs::RESPType) -> ::resp_rs::Result<Self> {{
+                let mut fields = ::resp_rs::convert::Fields::new(value)?;
+                ::std::result::Result::Ok({name} {{ {inits} }})
+            }}
+        }}",
+        name = st.name,
+        inits = inits
+    )
+    .parse()
+    .unwrap()
+}
+
+/// Encodes a struct as a RESP3 map from field keys to values. Call
+/// `to_resp2()` on the result for a flat key/value array.
+#[proc_macro_derive(ToResp, attributes(resp))]
+pub fn derive_to_resp(input: TokenStream) -> TokenStream {
+    let st = match parse_struct(input) {
+        Ok(st) => st,
+        Err(msg) => return compile_error(&msg),
+    };
+    let entries: String = st
+        .fields
+        .iter()
+        .map(|f| {
+            format!(
+                "(::resp_rs::ToResp::to_resp({:?}), ::resp_rs::ToResp::to_resp(&self.{})),",
+                f.key, f.ident
+            )
+        })
+        .collect();
+    format!(
+        "impl ::resp_rs::ToResp for {name} {{
+            fn to_resp(&self) -> ::resp_rs::RESPType {{
+                ::resp_rs::RESPType::Map(::std::vec![{entries}])
+            }}
+        }}",
+        name = st.name,
+        entries = entries
+    )
+    .parse()
+    .unwrap()
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({:?});", msg).parse().unwrap()
+}
+
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let mut tokens = input.into_iter();
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => match tokens.next() {
+                Some(TokenTree::Ident(name)) => break name.to_string(),
+                _ => return Err("expected a struct name".to_string()),
+            },
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "enum" => {
+                return Err("enums are not supported".to_string())
+            }
+            Some(_) => {}
+            None => return Err("expected a struct".to_string()),
+        }
+    };
+    match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => Ok(Struct {
+            name,
+            fields: parse_fields(group.stream())?,
+        }),
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+            Err("generic structs are not supported".to_string())
+        }
+        _ => Err("only structs with named fields are supported".to_string()),
+    }
+}
+
+/// Splits the body of a struct into fields at the commas that are not
+/// inside a type's angle brackets.
+fn parse_fields(body: TokenStream) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0;
+    let mut after_dash = false;
+    for tt in body {
+        if let TokenTree::Punct(p) = &tt {
+            match p.as_char() {
+                ',' if depth == 0 => {
+                    fields.push(parse_field(std::mem::take(&mut current))?);
+                    continue;
+                }
+                '<' => depth += 1,
+                // The `>` of `->` does not close an angle bracket.
+                '>' if !after_dash => depth -= 1,
+                _ => {}
+            }
+            after_dash = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+        } else {
+            after_dash = false;
+        }
+        current.push(tt);
+    }
+    if !current.is_empty() {
+        fields.push(parse_field(current)?);
+    }
+    Ok(fields)
+}
+
+fn parse_field(tokens: Vec<TokenTree>) -> Result<Field, String> {
+    let mut rename = None;
+    let mut tokens = tokens.into_iter().peekable();
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => match tokens.next() {
+                Some(TokenTree::Group(attr)) => {
+                    if let Some(key) = parse_rename(attr.stream())? {
+                        rename = Some(key);
+                    }
+                }
+                _ => return Err("malformed attribute".to_string()),
+            },
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+                if let Some(TokenTree::Group(_)) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+            Some(TokenTree::Ident(ident)) => {
+                let ident = ident.to_string();
+                let key = rename.unwrap_or_else(|| ident.trim_start_matches("r#").to_string());
+                return Ok(Field { ident, key });
+            }
+            _ => return Err("expected a field name".to_string()),
+        }
+    }
+}
+
+/// Returns the key of a `resp(rename = "...")` attribute, or `None` for
+/// other attributes such as doc comments.
+fn parse_rename(attr: TokenStream) -> Result<Option<String>, String> {
+    let mut tokens = attr.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "resp" => {}
+        _ => return Ok(None),
+    }
+    let args: Vec<TokenTree> = match tokens.next() {
+        Some(TokenTree::Group(group)) => group.stream().into_iter().collect(),
+        _ => return Err("expected #[resp(rename = \"...\")]".to_string()),
+    };
+    match &args[..] {
+        [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Literal(lit)]
+            if key.to_string() == "rename" && eq.as_char() == '=' =>
+        {
+            let lit = lit.to_string();
+            match lit.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                Some(key) if !key.contains('\\') => Ok(Some(key.to_string())),
+                _ => Err("rename takes a plain string literal".to_string()),
+            }
+        }
+        _ => Err("expected #[resp(rename = \"...\")]".to_string()),
+    }
+}
@@ -0,0 +1,579 @@
+//! Zero-copy parsing of frames held in memory.
+
+use crate::scan;
+use crate::{Error, ParseOptions, RESPType, Result, DEFAULT_MAX_DEPTH};
+
+/// A frame whose strings borrow from the buffer it was parsed from. The
+/// variants mirror [`RESPType`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RespRef<'a> {
+    SimpleString(&'a str),
+    Error(&'a str),
+    Integer(i64),
+    BulkString(&'a [u8]),
+    /// A `$?` streamed string, as the chunks it was sent in.
+    StreamedString(Vec<&'a [u8]>),
+    Null,
+    Array(Vec<RespRef<'a>>),
+    Map(Vec<(RespRef<'a>, RespRef<'a>)>),
+    Set(Vec<RespRef<'a>>),
+    Push(Vec<RespRef<'a>>),
+    Double(f64),
+    Boolean(bool),
+    BigNumber(&'a str),
+    Verbatim {
+        format: [u8; 3],
+        text: &'a [u8],
+    },
+    BulkError(&'a [u8]),
+    Attributed {
+        attrs: Vec<(RespRef<'a>, RespRef<'a>)>,
+        value: Box<RespRef<'a>>,
+    },
+}
+
+impl<'a> RespRef<'a> {
+    /// Copies the borrowed data into an owned value. Streamed strings are
+    /// joined into a single bulk string.
+    pub fn into_owned(self) -> RESPType {
+        match self {
+            RespRef::SimpleString(s) => RESPType::SimpleString(s.to_string()),
+            RespRef::Error(s) => RESPType::Error(s.to_string()),
+            RespRef::Integer(i) => RESPType::Integer(i),
+            RespRef::BulkString(buf) => RESPType::BulkString(buf.to_vec()),
+            RespRef::StreamedString(chunks) => RESPType::BulkString(chunks.concat()),
+            RespRef::Null => RESPType::Null,
+            RespRef::Array(items) => RESPType::Array(owned_items(items)),
+            RespRef::Map(entries) => RESPType::Map(owned_entries(entries)),
+            RespRef::Set(items) => RESPType::Set(owned_items(items)),
+            RespRef::Push(items) => RESPType::Push(owned_items(items)),
+            RespRef::Double(d) => RESPType::Double(d),
+            RespRef::Boolean(b) => RESPType::Boolean(b),
+            RespRef::BigNumber(n) => RESPType::BigNumber(n.to_string()),
+            RespRef::Verbatim { format, text } => RESPType::Verbatim {
+                format,
+                text: text.to_vec(),
+            },
+            RespRef::BulkError(buf) => RESPType::BulkError(buf.to_vec()),
+            RespRef::Attributed { attrs, value } => RESPType::Attributed {
+                attrs: owned_entries(attrs),
+                value: Box::new(value.into_owned()),
+            },
+        }
+    }
+}
+
+fn owned_items(items: Vec<RespRef<'_>>) -> Vec<RESPType> {
+    items.into_iter().map(RespRef::into_owned).collect()
+}
+
+fn owned_entries(entries: Vec<(RespRef<'_>, RespRef<'_>)>) -> Vec<(RESPType, RESPType)> {
+    entries
+        .into_iter()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
+/// Parses the first frame in `buf` without copying its payloads, returning
+/// it together with the number of bytes it occupies. Fails with
+/// [`Error::Incomplete`] if `buf` ends partway through the frame.
+///
+/// Parsing is strict: lines must end with CRLF, integers must be in
+/// canonical form and bulk payloads must be followed directly by CRLF.
+/// Aggregates may nest at most [`DEFAULT_MAX_DEPTH`] levels deep.
+pub fn parse_slice(buf: &[u8]) -> Result<(RespRef<'_>, usize)> {
+    let mut cursor = Cursor { buf, pos: 0 };
+    let value = cursor.value()?;
+    Ok((value, cursor.pos))
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn byte(&mut self) -> Result<u8> {
+        let b = *self
+            .buf
+            .get(self.pos)
+            .ok_or(Error::Incomplete { needed: Some(1) })?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    /// Returns the bytes up to the next CRLF.
+    fn line(&mut self) -> Result<&'a [u8]> {
+        let start = self.pos;
+        let rest = &self.buf[start..];
+        let end = scan::find_byte(b'\n', rest).ok_or(Error::Incomplete { needed: None })?;
+        self.pos += end + 1;
+        match scan::find_byte(b'\r', &rest[..end]) {
+            Some(i) if i + 1 == end => Ok(&rest[..i]),
+            Some(i) => Err(Error::UnexpectedToken {
+                byte: b'\r',
+                offset: (start + i) as u64,
+            }),
+            None => Err(Error::UnexpectedToken {
+                byte: b'\n',
+                offset: (start + end) as u64,
+            }),
+        }
+    }
+
+    fn str_line(&mut self) -> Result<&'a str> {
+        let offset = self.pos as u64;
+        let line = self.line()?;
+        std::str::from_utf8(line).map_err(|_| Error::BadString {
+            offset,
+            source: String::from_utf8(line.to_vec()).expect_err("invalid UTF-8"),
+        })
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        let start = self.pos as u64;
+        ParseOptions::strict().parse_int(self.str_line()?, start)
+    }
+
+    /// Reads a length line, returning `None` for the `?` of a streamed
+    /// frame.
+    fn length(&mut self) -> Result<Option<i64>> {
+        let start = self.pos as u64;
+        match self.str_line()? {
+            "?" => Ok(None),
+            line => ParseOptions::strict().parse_int(line, start).map(Some),
+        }
+    }
+
+    fn count(&mut self) -> Result<Option<usize>> {
+        let offset = self.pos as u64;
+        match self.length()? {
+            None => Ok(None),
+            Some(n) if n >= 0 => Ok(Some(n as usize)),
+            Some(length) => Err(Error::InvalidLength { length, offset }),
+        }
+    }
+
+    fn blob(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos + len;
+        if end + 2 > self.buf.len() {
+            return Err(Error::Incomplete {
+                needed: Some(end + 2 - self.buf.len()),
+            });
+        }
+        if &self.buf[end..end + 2] != b"\r\n" {
+            return Err(Error::UnexpectedToken {
+                byte: self.buf[end],
+                offset: end as u64,
+            });
+        }
+        let payload = &self.buf[self.pos..end];
+        self.pos = end + 2;
+        Ok(payload)
+    }
+
+    fn sized_blob(&mut self) -> Result<&'a [u8]> {
+        let offset = self.pos as u64;
+        match self.length()? {
+            Some(n) if n >= 0 => self.blob(n as usize),
+            Some(length) => Err(Error::InvalidLength { length, offset }),
+            None => Err(Error::MalformedInteger {
+                value: "?".to_string(),
+                offset,
+            }),
+        }
+    }
+
+    /// Consumes the `.` end marker of a streamed aggregate if it is next.
+    fn at_end(&mut self) -> Result<bool> {
+        match self.buf.get(self.pos) {
+            Some(b'.') => {
+                self.pos += 1;
+                let start = self.pos;
+                match self.line()?.first() {
+                    None => Ok(true),
+                    Some(&b) => Err(Error::UnexpectedToken {
+                        byte: b,
+                        offset: start as u64,
+                    }),
+                }
+            }
+            Some(_) => Ok(false),
+            None => Err(Error::Incomplete { needed: Some(1) }),
+        }
+    }
+
+    /// Reads one value. Aggregates are tracked on an explicit stack, so
+    /// only the depth limit bounds how deep the input may nest.
+    fn value(&mut self) -> Result<RespRef<'a>> {
+        let mut stack: Vec<Pending<'a>> = Vec::new();
+        loop {
+            let boundary = stack.last().is_some_and(Pending::at_streamed_boundary);
+            let mut done = if boundary && self.at_end()? {
+                close(&mut stack)
+            } else {
+                let marker = self.byte()?;
+                let offset = self.pos as u64;
+                let pending = match marker {
+                    b'*' => match self.length()? {
+                        Some(-1) => None,
+                        Some(length) if length < 0 => {
+                            return Err(Error::InvalidLength { length, offset })
+                        }
+                        count => Some(Pending::Items {
+                            marker,
+                            items: Vec::new(),
+                            remaining: count.map(|n| n as usize),
+                        }),
+                    },
+                    b'~' | b'>' => Some(Pending::Items {
+                        marker,
+                        items: Vec::new(),
+                        remaining: self.count()?,
+                    }),
+                    b'%' | b'|' => Some(Pending::Entries {
+                        attributes: marker == b'|',
+                        entries: Vec::new(),
+                        key: None,
+                        remaining: self.count()?,
+                    }),
+                    _ => None,
+                };
+                match pending {
+                    Some(pending) => {
+                        if stack.len() >= DEFAULT_MAX_DEPTH {
+                            return Err(Error::NestingTooDeep {
+                                limit: DEFAULT_MAX_DEPTH,
+                                offset: offset - 1,
+                            });
+                        }
+                        stack.push(pending);
+                        if stack.last().is_some_and(Pending::is_complete) {
+                            close(&mut stack)
+                        } else {
+                            None
+                        }
+                    }
+                    None if marker == b'*' => Some(RespRef::Null),
+                    None => Some(self.scalar(marker)?),
+                }
+            };
+            while let Some(value) = done.take() {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Pending::Attributed { .. }) => {
+                        if let Some(Pending::Attributed { attrs }) = stack.pop() {
+                            done = Some(RespRef::Attributed {
+                                attrs,
+                                value: Box::new(value),
+                            });
+                        }
+                    }
+                    Some(pending) => {
+                        pending.push(value);
+                        if pending.is_complete() {
+                            done = close(&mut stack);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the rest of a frame that is not an aggregate.
+    fn scalar(&mut self, marker: u8) -> Result<RespRef<'a>> {
+        let offset = self.pos as u64;
+        match marker {
+            b'+' => Ok(RespRef::SimpleString(self.str_line()?)),
+            b'-' => Ok(RespRef::Error(self.str_line()?)),
+            b':' => Ok(RespRef::Integer(self.integer()?)),
+            b'$' => match self.length()? {
+                None => {
+                    let mut chunks = Vec::new();
+                    loop {
+                        match self.byte()? {
+                            b';' => {}
+                            b => return Err(self.unexpected(b)),
+                        }
+                        let offset = self.pos as u64;
+                        match self.integer()? {
+                            0 => return Ok(RespRef::StreamedString(chunks)),
+                            n if n > 0 => chunks.push(self.blob(n as usize)?),
+                            length => return Err(Error::InvalidLength { length, offset }),
+                        }
+                    }
+                }
+                Some(-1) => Ok(RespRef::Null),
+                Some(n) if n >= 0 => Ok(RespRef::BulkString(self.blob(n as usize)?)),
+                Some(length) => Err(Error::InvalidLength { length, offset }),
+            },
+            b',' => {
+                let line = self.str_line()?;
+                line.parse::<f64>()
+                    .map(RespRef::Double)
+                    .map_err(|_| Error::MalformedDouble {
+                        value: line.to_string(),
+                        offset,
+                    })
+            }
+            b'#' => match self.line()? {
+                b"t" => Ok(RespRef::Boolean(true)),
+                b"f" => Ok(RespRef::Boolean(false)),
+                line => Err(Error::MalformedBoolean {
+                    value: String::from_utf8_lossy(line).into_owned(),
+                    offset,
+                }),
+            },
+            b'_' => {
+                let start = self.pos;
+                match self.line()?.first() {
+                    None => Ok(RespRef::Null),
+                    Some(&b) => Err(Error::UnexpectedToken {
+                        byte: b,
+                        offset: start as u64,
+                    }),
+                }
+            }
+            b'(' => {
+                let line = self.str_line()?;
+                let digits = line.strip_prefix(['-', '+']).unwrap_or(line);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(Error::MalformedInteger {
+                        value: line.to_string(),
+                        offset,
+                    });
+                }
+                Ok(RespRef::BigNumber(line))
+            }
+            b'=' => {
+                let payload = self.sized_blob()?;
+                let offset = self.pos as u64 - payload.len() as u64 - 2;
+                match payload.get(3) {
+                    Some(b':') => Ok(RespRef::Verbatim {
+                        format: [payload[0], payload[1], payload[2]],
+                        text: &payload[4..],
+                    }),
+                    _ => Err(Error::MalformedVerbatim {
+                        value: String::from_utf8_lossy(payload).into_owned(),
+                        offset,
+                    }),
+                }
+            }
+            b'!' => Ok(RespRef::BulkError(self.sized_blob()?)),
+            b => Err(self.unexpected(b)),
+        }
+    }
+
+    /// Rejects `byte`, the last byte read.
+    fn unexpected(&self, byte: u8) -> Error {
+        Error::UnexpectedToken {
+            byte,
+            offset: self.pos as u64 - 1,
+        }
+    }
+}
+
+/// An aggregate whose elements are still being read.
+enum Pending<'a> {
+    Items {
+        marker: u8,
+        items: Vec<RespRef<'a>>,
+        /// `None` for a streamed aggregate.
+        remaining: Option<usize>,
+    },
+    /// A map, or an attribute block if `attributes` is set.
+    Entries {
+        attributes: bool,
+        entries: Vec<(RespRef<'a>, RespRef<'a>)>,
+        key: Option<RespRef<'a>>,
+        remaining: Option<usize>,
+    },
+    /// Attributes waiting for the value they apply to.
+    Attributed {
+        attrs: Vec<(RespRef<'a>, RespRef<'a>)>,
+    },
+}
+
+impl<'a> Pending<'a> {
+    fn push(&mut self, value: RespRef<'a>) {
+        match self {
+            Pending::Items {
+                items, remaining, ..
+            } => {
+                items.push(value);
+                if let Some(n) = remaining {
+                    *n -= 1;
+                }
+            }
+            Pending::Entries {
+                entries,
+                key,
+                remaining,
+                ..
+            } => match key.take() {
+                None => *key = Some(value),
+                Some(k) => {
+                    entries.push((k, value));
+                    if let Some(n) = remaining {
+                        *n -= 1;
+                    }
+                }
+            },
+            Pending::Attributed { .. } => unreachable!("attributes take one value"),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        match self {
+            Pending::Items { remaining, .. } => *remaining == Some(0),
+            Pending::Entries { key, remaining, .. } => key.is_none() && *remaining == Some(0),
+            Pending::Attributed { .. } => false,
+        }
+    }
+
+    fn at_streamed_boundary(&self) -> bool {
+        matches!(
+            self,
+            Pending::Items {
+                remaining: None,
+                ..
+            } | Pending::Entries {
+                key: None,
+                remaining: None,
+                ..
+            }
+        )
+    }
+}
+
+/// Pops the finished aggregate on top of `stack` and returns its value. A
+/// finished attribute block is put back to wait for its value instead.
+fn close<'a>(stack: &mut Vec<Pending<'a>>) -> Option<RespRef<'a>> {
+    match stack.pop() {
+        Some(Pending::Items { marker, items, .. }) => Some(match marker {
+            b'*' => RespRef::Array(items),
+            b'~' => RespRef::Set(items),
+            _ => RespRef::Push(items),
+        }),
+        Some(Pending::Entries {
+            attributes: false,
+            entries,
+            ..
+        }) => Some(RespRef::Map(entries)),
+        Some(Pending::Entries { entries, .. }) => {
+            stack.push(Pending::Attributed { attrs: entries });
+            None
+        }
+        _ => unreachable!("only finished aggregates are closed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrows_payloads() {
+        let buf = b"*3\r\n$5\r\nhello\r\n+OK\r\n%1\r\n:1\r\n#t\r\n:2\r\n";
+        let (value, len) = parse_slice(buf).unwrap();
+        assert_eq!(len, buf.len() - 4);
+        assert_eq!(
+            value,
+            RespRef::Array(vec![
+                RespRef::BulkString(b"hello"),
+                RespRef::SimpleString("OK"),
+                RespRef::Map(vec![(RespRef::Integer(1), RespRef::Boolean(true))]),
+            ])
+        );
+        match &value {
+            RespRef::Array(items) => match items[0] {
+                RespRef::BulkString(payload) => {
+                    assert_eq!(payload.as_ptr(), buf[8..].as_ptr())
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn matches_owned_parser() {
+        let buf: &[u8] = b"|1\r\n+ttl\r\n:5\r\n*?\r\n,1.5\r\n(123\r\n=6\r\ntxt:hi\r\n!3\r\nERR\r\n$?\r\n;2\r\nab\r\n;1\r\nc\r\n;0\r\n_\r\n*-1\r\n.\r\n";
+        let (value, len) = parse_slice(buf).unwrap();
+        assert_eq!(len, buf.len());
+        assert_eq!(value.into_owned(), crate::parse_exact(buf).unwrap());
+    }
+
+    #[test]
+    fn reports_incomplete_frames() {
+        for (buf, needed) in &[
+            (&b""[..], Some(1)),
+            (b":12", None),
+            (b"$5\r\nab", Some(5)),
+            (b"*2\r\n:1\r\n", Some(1)),
+        ] {
+            match parse_slice(buf) {
+                Err(Error::Incomplete { needed: n }) => assert_eq!(n, *needed, "{:?}", buf),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_frames() {
+        assert!(parse_slice(b"$2\r\nabc\r\n").is_err());
+        assert!(parse_slice(b":+1\r\n").is_err());
+        assert!(parse_slice(b"?\r\n").is_err());
+    }
+
+    #[test]
+    fn requires_crlf() {
+        for input in [&b"+OK\n"[..], b"+O\rK\r\n", b"*1\r\n:1\n"] {
+            match parse_slice(input) {
+                Err(Error::UnexpectedToken { .. }) => {}
+                other => panic!("unexpected {:?} for {:?}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let frame = |depth: usize| format!("{}:1\r\n", "*1\r\n".repeat(depth));
+        assert!(parse_slice(frame(DEFAULT_MAX_DEPTH).as_bytes()).is_ok());
+        match parse_slice(frame(1_000_000).as_bytes()) {
+            Err(Error::NestingTooDeep { limit, .. }) => assert_eq!(limit, DEFAULT_MAX_DEPTH),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_offsets() {
+        match parse_slice(b"*2\r\n:1\r\n?\r\n") {
+            Err(Error::UnexpectedToken {
+                byte: b'?',
+                offset: 8,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let input = b"*1\r\n,x\r\n";
+        for result in [
+            parse_slice(input).map(|_| ()),
+            crate::parse_exact(input).map(|_| ()),
+        ] {
+            match result {
+                Err(Error::MalformedDouble { offset: 5, .. }) => {}
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        let input = b"*1\r\n=5\r\nabcde\r\n";
+        for result in [
+            parse_slice(input).map(|_| ()),
+            crate::parse_exact(input).map(|_| ()),
+        ] {
+            match result {
+                Err(Error::MalformedVerbatim { offset: 8, .. }) => {}
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+}
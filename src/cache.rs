@@ -0,0 +1,257 @@
+use crate::RESPType;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Memoizes replies to read-only commands, keyed by the exact encoded
+/// request bytes.
+///
+/// Entries expire after a fixed TTL and the cache is bounded both by entry
+/// count and by the estimated heap size of the stored replies; when a limit
+/// is hit the oldest entries are evicted first. Each entry remembers the keys
+/// its command read, so that client-side caching invalidation messages can
+/// drop the affected replies.
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: usize,
+    entries: HashMap<Vec<u8>, Entry>,
+    /// Requests in insertion order, tagged with the sequence number of the
+    /// entry they were inserted as. Removing an entry leaves its tag behind,
+    /// to be skipped once it reaches the front.
+    order: VecDeque<(u64, Vec<u8>)>,
+    next_seq: u64,
+    by_key: HashMap<Vec<u8>, HashSet<Vec<u8>>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    reply: RESPType,
+    keys: Vec<Vec<u8>>,
+    expires: Instant,
+    size: usize,
+    seq: u64,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration, max_entries: usize, max_bytes: usize) -> ResponseCache {
+        ResponseCache {
+            ttl,
+            max_entries,
+            max_bytes,
+            bytes: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            next_seq: 0,
+            by_key: HashMap::new(),
+        }
+    }
+
+    /// Returns the cached reply for `request` if present and not expired.
+    pub fn get(&mut self, request: &[u8]) -> Option<&RESPType> {
+        let expired = match self.entries.get(request) {
+            Some(entry) => entry.expires <= Instant::now(),
+            None => return None,
+        };
+        if expired {
+            self.remove(request);
+            return None;
+        }
+        self.entries.get(request).map(|e| &e.reply)
+    }
+
+    /// Caches `reply` for `request`, recording the keys the command read.
+    /// Replies larger than the byte budget are not cached.
+    pub fn insert<I, K>(&mut self, request: Vec<u8>, keys: I, reply: RESPType)
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<[u8]>,
+    {
+        self.remove(&request);
+        let size = request.len() + reply.heap_size();
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+        while self.entries.len() >= self.max_entries || self.bytes + size > self.max_bytes {
+            match self.order.pop_front() {
+                Some((seq, oldest)) => {
+                    if self.entries.get(&oldest).is_some_and(|e| e.seq == seq) {
+                        self.remove(&oldest);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.as_ref().to_vec()).collect();
+        for key in &keys {
+            self.by_key
+                .entry(key.clone())
+                .or_default()
+                .insert(request.clone());
+        }
+        self.bytes += size;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.push_back((seq, request.clone()));
+        self.entries.insert(
+            request,
+            Entry {
+                reply,
+                keys,
+                expires: Instant::now() + self.ttl,
+                size,
+                seq,
+            },
+        );
+    }
+
+    /// Drops every cached reply that read `key`.
+    pub fn invalidate_key(&mut self, key: &[u8]) {
+        if let Some(requests) = self.by_key.remove(key) {
+            for request in requests {
+                self.remove(&request);
+            }
+        }
+    }
+
+    /// Applies the payload of a client tracking `invalidate` message: an
+    /// array of key names, or null when the server flushed everything.
+    pub fn apply_invalidation(&mut self, payload: &RESPType) {
+        match payload {
+            RESPType::Null => self.clear(),
+            RESPType::Array(keys) => {
+                for key in keys {
+                    match key {
+                        RESPType::BulkString(k) => self.invalidate_key(k),
+                        RESPType::SimpleString(k) => self.invalidate_key(k.as_bytes()),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.by_key.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Estimated number of bytes held by cached requests and replies.
+    pub fn size_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn remove(&mut self, request: &[u8]) {
+        let entry = match self.entries.remove(request) {
+            Some(entry) => entry,
+            None => return,
+        };
+        self.bytes -= entry.size;
+        // Drop stale tags in bulk once they outnumber the live ones, which
+        // keeps removal O(1) amortized.
+        if self.order.len() > 2 * self.entries.len() + 16 {
+            let entries = &self.entries;
+            self.order
+                .retain(|(seq, r)| entries.get(r).is_some_and(|e| e.seq == *seq));
+        }
+        for key in entry.keys {
+            if let Some(requests) = self.by_key.get_mut(&key) {
+                requests.remove(request);
+                if requests.is_empty() {
+                    self.by_key.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+
+    fn cache() -> ResponseCache {
+        ResponseCache::new(Duration::from_secs(60), 2, 1024)
+    }
+
+    #[test]
+    fn returns_cached_reply() {
+        let mut cache = cache();
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("1"));
+        assert_eq!(cache.get(b"GET a"), Some(&bulk("1")));
+        assert_eq!(cache.get(b"GET b"), None);
+    }
+
+    #[test]
+    fn expires_entries() {
+        let mut cache = ResponseCache::new(Duration::from_secs(0), 2, 1024);
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("1"));
+        assert_eq!(cache.get(b"GET a"), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn evicts_oldest_when_full() {
+        let mut cache = cache();
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("1"));
+        cache.insert(b"GET b".to_vec(), [b"b"], bulk("2"));
+        cache.insert(b"GET c".to_vec(), [b"c"], bulk("3"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(b"GET a"), None);
+        assert_eq!(cache.get(b"GET c"), Some(&bulk("3")));
+    }
+
+    #[test]
+    fn respects_byte_budget() {
+        let mut cache = ResponseCache::new(Duration::from_secs(60), 10, 16);
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("this is far too long"));
+        assert!(cache.is_empty());
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("1"));
+        assert_eq!(cache.size_bytes(), 6);
+    }
+
+    #[test]
+    fn skips_removed_entries_when_evicting() {
+        let mut cache = cache();
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("1"));
+        cache.insert(b"GET b".to_vec(), [b"b"], bulk("2"));
+        // Reinserting moves `GET a` to the back, so `GET b` is now oldest.
+        cache.insert(b"GET a".to_vec(), [b"a"], bulk("3"));
+        cache.insert(b"GET c".to_vec(), [b"c"], bulk("4"));
+        assert_eq!(cache.get(b"GET b"), None);
+        assert_eq!(cache.get(b"GET a"), Some(&bulk("3")));
+
+        for i in 0..1000 {
+            cache.insert(format!("GET {}", i).into_bytes(), [b"k"], bulk("x"));
+            cache.invalidate_key(b"k");
+        }
+        assert!(cache.order.len() <= 2 * cache.len() + 17);
+    }
+
+    #[test]
+    fn invalidation_drops_dependent_entries() {
+        let mut cache = cache();
+        cache.insert(b"MGET a b".to_vec(), [b"a", b"b"], bulk("x"));
+        cache.insert(b"GET c".to_vec(), [b"c"], bulk("y"));
+        cache.apply_invalidation(&RESPType::Array(vec![bulk("b")]));
+        assert_eq!(cache.get(b"MGET a b"), None);
+        assert_eq!(cache.get(b"GET c"), Some(&bulk("y")));
+
+        cache.apply_invalidation(&RESPType::Null);
+        assert!(cache.is_empty());
+        assert_eq!(cache.size_bytes(), 0);
+    }
+}
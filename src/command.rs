@@ -0,0 +1,118 @@
+//! Requests as a server receives them: an array of bulk strings whose
+//! first element names the command.
+
+use crate::{Error, RESPType, Result};
+
+/// A validated request, such as the array sent by [`crate::encode_command`]
+/// or returned by [`crate::inline::parse_inline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    name: String,
+    parts: Vec<Vec<u8>>,
+}
+
+impl Command {
+    /// The command name in upper case, e.g. `GET` for `get`. Bytes that
+    /// are not valid UTF-8 are replaced.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The arguments after the command name.
+    pub fn args(&self) -> impl ExactSizeIterator<Item = &[u8]> + '_ {
+        self.parts[1..].iter().map(Vec::as_slice)
+    }
+
+    /// The `n`th argument after the command name.
+    pub fn arg(&self, n: usize) -> Option<&[u8]> {
+        self.parts.get(n + 1).map(Vec::as_slice)
+    }
+
+    /// The command name as sent, followed by its arguments.
+    pub fn into_parts(self) -> Vec<Vec<u8>> {
+        self.parts
+    }
+}
+
+/// Checks that `value` is a non-empty array of bulk strings and wraps it
+/// as a [`Command`].
+pub fn parse_command(value: RESPType) -> Result<Command> {
+    let items = match value {
+        RESPType::Array(items) => items,
+        other => {
+            return Err(Error::TypeMismatch {
+                expected: "array",
+                actual: other.kind(),
+            })
+        }
+    };
+    let parts = items
+        .into_iter()
+        .map(|item| match item {
+            RESPType::BulkString(arg) => Ok(arg),
+            other => Err(Error::TypeMismatch {
+                expected: "bulk string",
+                actual: other.kind(),
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let name = match parts.first() {
+        Some(name) => String::from_utf8_lossy(name).to_ascii_uppercase(),
+        None => return Err(Error::InvalidCommand("empty command".to_string())),
+    };
+    Ok(Command { name, parts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inline::parse_inline;
+    use crate::{Parser, RESPKind};
+    use std::io::Read;
+
+    #[test]
+    fn parses_requests() {
+        let input = crate::encode_command(&["set", "key", "value"]);
+        let value = Parser::new(input.bytes()).parse_next().unwrap();
+        let command = parse_command(value).unwrap();
+        assert_eq!(command.name(), "SET");
+        assert_eq!(
+            command.args().collect::<Vec<_>>(),
+            vec![&b"key"[..], &b"value"[..]]
+        );
+        assert_eq!(command.arg(1), Some(&b"value"[..]));
+        assert_eq!(command.arg(2), None);
+        assert_eq!(command.into_parts()[0], b"set".to_vec());
+
+        let (value, _) = parse_inline(b"ping\r\n").unwrap().unwrap();
+        let command = parse_command(value).unwrap();
+        assert_eq!(command.name(), "PING");
+        assert_eq!(command.args().len(), 0);
+    }
+
+    #[test]
+    fn rejects_other_frames() {
+        match parse_command(RESPType::SimpleString("PING".to_string())) {
+            Err(Error::TypeMismatch {
+                expected: "array",
+                actual: RESPKind::SimpleString,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let value = RESPType::Array(vec![
+            RESPType::BulkString(b"GET".to_vec()),
+            RESPType::Integer(1),
+        ]);
+        match parse_command(value) {
+            Err(Error::TypeMismatch {
+                expected: "bulk string",
+                actual: RESPKind::Integer,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_command(RESPType::Array(Vec::new())) {
+            Err(Error::InvalidCommand(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
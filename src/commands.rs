@@ -0,0 +1,473 @@
+//! A table of standard Redis commands with their arity and flags, as
+//! reported by `COMMAND INFO`, so that servers and proxies can reject
+//! malformed requests before dispatching them.
+//!
+//! Key positions follow the same convention as Redis: the first and last
+//! argument holding a key, with a negative last position counting from the
+//! end, and the step between keys.
+//!
+//! Only top-level commands are listed; subcommands such as `CONFIG GET`
+//! are checked against their container's arity.
+
+use crate::{Command, Error, Result};
+
+use self::Flag::*;
+
+/// A command flag, named after the one Redis reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flag {
+    Admin,
+    Blocking,
+    DenyOom,
+    Fast,
+    Loading,
+    /// The key positions cannot be taken from the table alone.
+    MovableKeys,
+    NoScript,
+    PubSub,
+    ReadOnly,
+    Stale,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    /// The number of arguments including the command name. A negative
+    /// arity `-n` means at least `n`.
+    pub arity: i32,
+    pub flags: &'static [Flag],
+    /// Position of the first key, or 0 for commands without fixed keys.
+    pub first_key: i32,
+    pub last_key: i32,
+    pub key_step: i32,
+}
+
+impl CommandSpec {
+    pub fn has_flag(&self, flag: Flag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    /// Whether `argc` arguments, counting the name, satisfy the arity.
+    pub fn accepts(&self, argc: usize) -> bool {
+        if self.arity < 0 {
+            argc >= self.arity.unsigned_abs() as usize
+        } else {
+            argc == self.arity as usize
+        }
+    }
+}
+
+/// Looks up a command by name, ignoring case.
+pub fn lookup(name: &str) -> Option<&'static CommandSpec> {
+    let name = name.to_ascii_uppercase();
+    COMMANDS
+        .binary_search_by(|spec| spec.name.cmp(name.as_str()))
+        .ok()
+        .map(|i| &COMMANDS[i])
+}
+
+/// Every command in the table, sorted by name.
+pub fn commands() -> &'static [CommandSpec] {
+    COMMANDS
+}
+
+impl Command {
+    /// The table entry for this command, if it is a known one.
+    pub fn spec(&self) -> Option<&'static CommandSpec> {
+        lookup(self.name())
+    }
+
+    /// Checks that the command is known and has an acceptable number of
+    /// arguments, with the same wording as the errors Redis replies with.
+    pub fn validate_arity(&self) -> Result<()> {
+        let spec = match self.spec() {
+            Some(spec) => spec,
+            None => {
+                return Err(Error::InvalidCommand(format!(
+                    "unknown command '{}'",
+                    self.name()
+                )))
+            }
+        };
+        if !spec.accepts(self.args().len() + 1) {
+            return Err(Error::InvalidCommand(format!(
+                "wrong number of arguments for '{}' command",
+                self.name().to_ascii_lowercase()
+            )));
+        }
+        Ok(())
+    }
+
+    /// The keys the command refers to, in order, e.g. for routing it to a
+    /// cluster node. Commands with movable keys, such as `EVAL`, are
+    /// handled by reading their `numkeys` argument or keywords.
+    pub fn keys(&self) -> Result<Vec<&[u8]>> {
+        self.validate_arity()?;
+        let spec = self.spec().unwrap();
+        // Positions count the command name as 0.
+        let argv: Vec<&[u8]> = std::iter::once(self.name().as_bytes())
+            .chain(self.args())
+            .collect();
+        let keys = match spec.name {
+            "EVAL" | "EVALSHA" => {
+                let n = self.numkeys(&argv, 2)?;
+                argv[3..3 + n].to_vec()
+            }
+            "ZINTERSTORE" | "ZUNIONSTORE" => {
+                let n = self.numkeys(&argv, 2)?;
+                let mut keys = vec![argv[1]];
+                keys.extend(&argv[3..3 + n]);
+                keys
+            }
+            "XREAD" => {
+                let streams = argv
+                    .iter()
+                    .position(|arg| arg.eq_ignore_ascii_case(b"STREAMS"));
+                match streams {
+                    Some(i) if (argv.len() - i - 1).is_multiple_of(2) => {
+                        let n = (argv.len() - i - 1) / 2;
+                        argv[i + 1..i + 1 + n].to_vec()
+                    }
+                    _ => return Err(self.malformed("streams")),
+                }
+            }
+            "SORT" => {
+                let mut keys = vec![argv[1]];
+                let store = argv[2..]
+                    .iter()
+                    .position(|arg| arg.eq_ignore_ascii_case(b"STORE"));
+                if let Some(dest) = store.and_then(|i| argv.get(i + 3)) {
+                    keys.push(dest);
+                }
+                keys
+            }
+            _ if spec.first_key == 0 => Vec::new(),
+            _ => {
+                let argc = argv.len() as i32;
+                let last = if spec.last_key < 0 {
+                    argc + spec.last_key
+                } else {
+                    spec.last_key.min(argc - 1)
+                };
+                (spec.first_key..=last)
+                    .step_by(spec.key_step as usize)
+                    .map(|i| argv[i as usize])
+                    .collect()
+            }
+        };
+        Ok(keys)
+    }
+
+    /// Reads the key count at `pos`, checking that that many keys follow.
+    fn numkeys(&self, argv: &[&[u8]], pos: usize) -> Result<usize> {
+        let n = std::str::from_utf8(argv[pos])
+            .ok()
+            .and_then(|n| n.parse::<usize>().ok())
+            .ok_or_else(|| self.malformed("numkeys"))?;
+        if n > argv.len() - pos - 1 {
+            return Err(self.malformed("numkeys"));
+        }
+        Ok(n)
+    }
+
+    fn malformed(&self, what: &str) -> Error {
+        Error::InvalidCommand(format!(
+            "invalid {} for '{}' command",
+            what,
+            self.name().to_ascii_lowercase()
+        ))
+    }
+}
+
+const fn spec(name: &'static str, arity: i32, flags: &'static [Flag]) -> CommandSpec {
+    CommandSpec {
+        name,
+        arity,
+        flags,
+        first_key: 0,
+        last_key: 0,
+        key_step: 0,
+    }
+}
+
+impl CommandSpec {
+    const fn keys(self, first_key: i32, last_key: i32, key_step: i32) -> CommandSpec {
+        CommandSpec {
+            first_key,
+            last_key,
+            key_step,
+            ..self
+        }
+    }
+}
+
+const COMMANDS: &[CommandSpec] = &[
+    spec("APPEND", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("AUTH", -2, &[NoScript, Loading, Stale, Fast]),
+    spec("BGREWRITEAOF", 1, &[Admin, NoScript]),
+    spec("BGSAVE", -1, &[Admin, NoScript]),
+    spec("BITCOUNT", -2, &[ReadOnly]).keys(1, 1, 1),
+    spec("BITFIELD", -2, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("BITOP", -4, &[Write, DenyOom]).keys(2, -1, 1),
+    spec("BITPOS", -3, &[ReadOnly]).keys(1, 1, 1),
+    spec("BLMOVE", 6, &[Write, DenyOom, NoScript, Blocking]).keys(1, 2, 1),
+    spec("BLPOP", -3, &[Write, NoScript, Blocking]).keys(1, -2, 1),
+    spec("BRPOP", -3, &[Write, NoScript, Blocking]).keys(1, -2, 1),
+    spec("BRPOPLPUSH", 4, &[Write, DenyOom, NoScript, Blocking]).keys(1, 2, 1),
+    spec("BZPOPMAX", -3, &[Write, NoScript, Fast, Blocking]).keys(1, -2, 1),
+    spec("BZPOPMIN", -3, &[Write, NoScript, Fast, Blocking]).keys(1, -2, 1),
+    spec("CLIENT", -2, &[Admin, NoScript, Loading, Stale]),
+    spec("CLUSTER", -2, &[]),
+    spec("COMMAND", -1, &[Loading, Stale]),
+    spec("CONFIG", -2, &[Admin, NoScript, Loading, Stale]),
+    spec("COPY", -3, &[Write, DenyOom]).keys(1, 2, 1),
+    spec("DBSIZE", 1, &[ReadOnly, Fast]),
+    spec("DECR", 2, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("DECRBY", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("DEL", -2, &[Write]).keys(1, -1, 1),
+    spec("DISCARD", 1, &[NoScript, Loading, Stale, Fast]),
+    spec("DUMP", 2, &[ReadOnly]).keys(1, 1, 1),
+    spec("ECHO", 2, &[Fast]),
+    spec("EVAL", -3, &[NoScript, MovableKeys]),
+    spec("EVALSHA", -3, &[NoScript, MovableKeys]),
+    spec("EXEC", 1, &[NoScript, Loading, Stale]),
+    spec("EXISTS", -2, &[ReadOnly, Fast]).keys(1, -1, 1),
+    spec("EXPIRE", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("EXPIREAT", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("FLUSHALL", -1, &[Write]),
+    spec("FLUSHDB", -1, &[Write]),
+    spec("GEOADD", -5, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("GEODIST", -4, &[ReadOnly]).keys(1, 1, 1),
+    spec("GEOHASH", -2, &[ReadOnly]).keys(1, 1, 1),
+    spec("GEOPOS", -2, &[ReadOnly]).keys(1, 1, 1),
+    spec("GEOSEARCH", -7, &[ReadOnly]).keys(1, 1, 1),
+    spec("GET", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("GETDEL", 2, &[Write, Fast]).keys(1, 1, 1),
+    spec("GETEX", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("GETRANGE", 4, &[ReadOnly]).keys(1, 1, 1),
+    spec("GETSET", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HDEL", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("HELLO", -1, &[NoScript, Loading, Stale, Fast]),
+    spec("HEXISTS", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("HGET", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("HGETALL", 2, &[ReadOnly]).keys(1, 1, 1),
+    spec("HINCRBY", 4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HINCRBYFLOAT", 4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HKEYS", 2, &[ReadOnly]).keys(1, 1, 1),
+    spec("HLEN", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("HMGET", -3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("HMSET", -4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HSCAN", -3, &[ReadOnly]).keys(1, 1, 1),
+    spec("HSET", -4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HSETNX", 4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("HSTRLEN", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("HVALS", 2, &[ReadOnly]).keys(1, 1, 1),
+    spec("INCR", 2, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("INCRBY", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("INCRBYFLOAT", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("INFO", -1, &[Loading, Stale]),
+    spec("KEYS", 2, &[ReadOnly]),
+    spec("LASTSAVE", 1, &[Loading, Stale, Fast]),
+    spec("LINDEX", 3, &[ReadOnly]).keys(1, 1, 1),
+    spec("LINSERT", 5, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("LLEN", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("LMOVE", 5, &[Write, DenyOom]).keys(1, 2, 1),
+    spec("LPOP", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("LPOS", -3, &[ReadOnly]).keys(1, 1, 1),
+    spec("LPUSH", -3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("LPUSHX", -3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("LRANGE", 4, &[ReadOnly]).keys(1, 1, 1),
+    spec("LREM", 4, &[Write]).keys(1, 1, 1),
+    spec("LSET", 4, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("LTRIM", 4, &[Write]).keys(1, 1, 1),
+    spec("MGET", -2, &[ReadOnly, Fast]).keys(1, -1, 1),
+    spec("MONITOR", 1, &[Admin, NoScript, Loading, Stale]),
+    spec("MOVE", 3, &[Write, Fast]).keys(1, 1, 1),
+    spec("MSET", -3, &[Write, DenyOom]).keys(1, -1, 2),
+    spec("MSETNX", -3, &[Write, DenyOom]).keys(1, -1, 2),
+    spec("MULTI", 1, &[NoScript, Loading, Stale, Fast]),
+    spec("OBJECT", -2, &[ReadOnly]).keys(2, 2, 1),
+    spec("PERSIST", 2, &[Write, Fast]).keys(1, 1, 1),
+    spec("PEXPIRE", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("PEXPIREAT", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("PFADD", -2, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("PFCOUNT", -2, &[ReadOnly]).keys(1, -1, 1),
+    spec("PFMERGE", -2, &[Write, DenyOom]).keys(1, -1, 1),
+    spec("PING", -1, &[Fast]),
+    spec("PSETEX", 4, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("PSUBSCRIBE", -2, &[PubSub, NoScript, Loading, Stale]),
+    spec("PTTL", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("PUBLISH", 3, &[PubSub, Loading, Stale, Fast]),
+    spec("PUNSUBSCRIBE", -1, &[PubSub, NoScript, Loading, Stale]),
+    spec("QUIT", -1, &[NoScript, Loading, Stale, Fast]),
+    spec("RANDOMKEY", 1, &[ReadOnly]),
+    spec("RENAME", 3, &[Write]).keys(1, 2, 1),
+    spec("RENAMENX", 3, &[Write, Fast]).keys(1, 2, 1),
+    spec("RESTORE", -4, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("RPOP", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("RPOPLPUSH", 3, &[Write, DenyOom]).keys(1, 2, 1),
+    spec("RPUSH", -3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("RPUSHX", -3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("SADD", -3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("SAVE", 1, &[Admin, NoScript]),
+    spec("SCAN", -2, &[ReadOnly]),
+    spec("SCARD", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("SCRIPT", -2, &[NoScript]),
+    spec("SDIFF", -2, &[ReadOnly]).keys(1, -1, 1),
+    spec("SDIFFSTORE", -3, &[Write, DenyOom]).keys(1, -1, 1),
+    spec("SELECT", 2, &[Loading, Stale, Fast]),
+    spec("SET", -3, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("SETEX", 4, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("SETNX", 3, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("SETRANGE", 4, &[Write, DenyOom]).keys(1, 1, 1),
+    spec("SHUTDOWN", -1, &[Admin, NoScript, Loading, Stale]),
+    spec("SINTER", -2, &[ReadOnly]).keys(1, -1, 1),
+    spec("SINTERSTORE", -3, &[Write, DenyOom]).keys(1, -1, 1),
+    spec("SISMEMBER", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("SMEMBERS", 2, &[ReadOnly]).keys(1, 1, 1),
+    spec("SMISMEMBER", -3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("SMOVE", 4, &[Write, Fast]).keys(1, 2, 1),
+    spec("SORT", -2, &[Write, DenyOom, MovableKeys]).keys(1, 1, 1),
+    spec("SPOP", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("SRANDMEMBER", -2, &[ReadOnly]).keys(1, 1, 1),
+    spec("SREM", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("SSCAN", -3, &[ReadOnly]).keys(1, 1, 1),
+    spec("STRLEN", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("SUBSCRIBE", -2, &[PubSub, NoScript, Loading, Stale]),
+    spec("SUNION", -2, &[ReadOnly]).keys(1, -1, 1),
+    spec("SUNIONSTORE", -3, &[Write, DenyOom]).keys(1, -1, 1),
+    spec("SWAPDB", 3, &[Write, Fast]),
+    spec("TIME", 1, &[Loading, Stale, Fast]),
+    spec("TOUCH", -2, &[ReadOnly, Fast]).keys(1, -1, 1),
+    spec("TTL", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("TYPE", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("UNLINK", -2, &[Write, Fast]).keys(1, -1, 1),
+    spec("UNSUBSCRIBE", -1, &[PubSub, NoScript, Loading, Stale]),
+    spec("UNWATCH", 1, &[NoScript, Loading, Stale, Fast]),
+    spec("WAIT", 3, &[NoScript]),
+    spec("WATCH", -2, &[NoScript, Loading, Stale, Fast]).keys(1, -1, 1),
+    spec("XACK", -4, &[Write, Fast]).keys(1, 1, 1),
+    spec("XADD", -5, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("XDEL", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("XLEN", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("XRANGE", -4, &[ReadOnly]).keys(1, 1, 1),
+    spec("XREAD", -4, &[ReadOnly, Blocking, MovableKeys]),
+    spec("XREVRANGE", -4, &[ReadOnly]).keys(1, 1, 1),
+    spec("XTRIM", -4, &[Write]).keys(1, 1, 1),
+    spec("ZADD", -4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("ZCARD", 2, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZCOUNT", 4, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZINCRBY", 4, &[Write, DenyOom, Fast]).keys(1, 1, 1),
+    spec("ZINTERSTORE", -4, &[Write, DenyOom, MovableKeys]),
+    spec("ZMSCORE", -3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZPOPMAX", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("ZPOPMIN", -2, &[Write, Fast]).keys(1, 1, 1),
+    spec("ZRANGE", -4, &[ReadOnly]).keys(1, 1, 1),
+    spec("ZRANGEBYSCORE", -4, &[ReadOnly]).keys(1, 1, 1),
+    spec("ZRANK", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZREM", -3, &[Write, Fast]).keys(1, 1, 1),
+    spec("ZREVRANGE", 4, &[ReadOnly]).keys(1, 1, 1),
+    spec("ZREVRANK", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZSCAN", -3, &[ReadOnly]).keys(1, 1, 1),
+    spec("ZSCORE", 3, &[ReadOnly, Fast]).keys(1, 1, 1),
+    spec("ZUNIONSTORE", -4, &[Write, DenyOom, MovableKeys]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_command;
+    use crate::RESPType;
+
+    fn command(args: &[&str]) -> Command {
+        let args = args
+            .iter()
+            .map(|arg| RESPType::BulkString(arg.as_bytes().to_vec()))
+            .collect();
+        parse_command(RESPType::Array(args)).unwrap()
+    }
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(COMMANDS.windows(2).all(|w| w[0].name < w[1].name));
+    }
+
+    #[test]
+    fn looks_up_commands() {
+        let get = lookup("get").unwrap();
+        assert_eq!(get.arity, 2);
+        assert!(get.has_flag(ReadOnly));
+        assert!(!get.has_flag(Write));
+        assert!(lookup("SET").unwrap().has_flag(Write));
+        assert_eq!(lookup("NOSUCH"), None);
+    }
+
+    #[test]
+    fn validates_arity() {
+        assert!(command(&["get", "k"]).validate_arity().is_ok());
+        assert!(command(&["SET", "k", "v", "EX", "10"])
+            .validate_arity()
+            .is_ok());
+        assert!(command(&["PING"]).validate_arity().is_ok());
+        let err = command(&["GET"]).validate_arity().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid command: wrong number of arguments for 'get' command"
+        );
+        assert!(command(&["GET", "a", "b"]).validate_arity().is_err());
+        assert!(command(&["SET", "k"]).validate_arity().is_err());
+        let err = command(&["frob"]).validate_arity().unwrap_err();
+        assert_eq!(err.to_string(), "Invalid command: unknown command 'FROB'");
+    }
+
+    fn keys(args: &[&str]) -> Vec<String> {
+        command(args)
+            .keys()
+            .unwrap()
+            .into_iter()
+            .map(|key| String::from_utf8(key.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn extracts_fixed_keys() {
+        assert_eq!(keys(&["GET", "a"]), vec!["a"]);
+        assert_eq!(keys(&["DEL", "a", "b", "c"]), vec!["a", "b", "c"]);
+        assert_eq!(keys(&["MSET", "a", "1", "b", "2"]), vec!["a", "b"]);
+        assert_eq!(keys(&["BLPOP", "a", "b", "0"]), vec!["a", "b"]);
+        assert_eq!(keys(&["BITOP", "AND", "d", "a", "b"]), vec!["d", "a", "b"]);
+        assert_eq!(keys(&["RENAME", "a", "b"]), vec!["a", "b"]);
+        assert_eq!(keys(&["OBJECT", "ENCODING", "a"]), vec!["a"]);
+        assert!(keys(&["PING"]).is_empty());
+    }
+
+    #[test]
+    fn extracts_movable_keys() {
+        assert_eq!(
+            keys(&["EVAL", "return 1", "2", "a", "b", "arg"]),
+            vec!["a", "b"]
+        );
+        assert!(keys(&["EVALSHA", "abc", "0"]).is_empty());
+        assert_eq!(
+            keys(&["ZUNIONSTORE", "d", "2", "a", "b", "WEIGHTS", "1", "2"]),
+            vec!["d", "a", "b"]
+        );
+        assert_eq!(
+            keys(&["XREAD", "COUNT", "2", "streams", "a", "b", "0", "0"]),
+            vec!["a", "b"]
+        );
+        assert_eq!(keys(&["SORT", "a", "STORE", "d"]), vec!["a", "d"]);
+        assert_eq!(keys(&["SORT", "a", "LIMIT", "0", "1"]), vec!["a"]);
+    }
+
+    #[test]
+    fn rejects_bad_key_counts() {
+        assert!(command(&["EVAL", "s", "3", "a"]).keys().is_err());
+        assert!(command(&["EVAL", "s", "x"]).keys().is_err());
+        assert!(command(&["XREAD", "STREAMS", "a", "b", "0"])
+            .keys()
+            .is_err());
+        assert!(command(&["GET"]).keys().is_err());
+    }
+}
@@ -0,0 +1,579 @@
+//! Conversion between replies and Rust types.
+
+use crate::reply::invalid;
+use crate::{Error, RESPType, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::Hash;
+
+/// A type that can be decoded from a reply. Attributes attached to the
+/// reply are ignored, and a `WRONGTYPE` error reply is reported as
+/// [`Error::WrongType`].
+pub trait FromResp: Sized {
+    fn from_resp(value: RESPType) -> Result<Self>;
+}
+
+impl RESPType {
+    /// Decodes the value into `T`, e.g. `reply.convert::<Vec<String>>()`.
+    pub fn convert<T: FromResp>(self) -> Result<T> {
+        T::from_resp(self)
+    }
+}
+
+fn strip(value: RESPType) -> RESPType {
+    match value {
+        RESPType::Attributed { value, .. } => strip(*value),
+        other => other,
+    }
+}
+
+impl FromResp for RESPType {
+    fn from_resp(value: RESPType) -> Result<RESPType> {
+        Ok(value)
+    }
+}
+
+impl FromResp for Vec<u8> {
+    fn from_resp(value: RESPType) -> Result<Vec<u8>> {
+        match strip(value) {
+            RESPType::BulkString(buf) => Ok(buf),
+            RESPType::SimpleString(s) => Ok(s.into_bytes()),
+            RESPType::Verbatim { text, .. } => Ok(text),
+            other => Err(invalid("string", &other)),
+        }
+    }
+}
+
+impl FromResp for String {
+    fn from_resp(value: RESPType) -> Result<String> {
+        match strip(value) {
+            RESPType::SimpleString(s) => Ok(s),
+            other => String::from_utf8(Vec::<u8>::from_resp(other)?)
+                .map_err(|err| Error::InvalidReply(format!("invalid UTF-8: {}", err))),
+        }
+    }
+}
+
+/// Accepts integers as well as strings holding one, which is how some
+/// commands report numbers.
+impl FromResp for i64 {
+    fn from_resp(value: RESPType) -> Result<i64> {
+        match strip(value) {
+            RESPType::Integer(i) => Ok(i),
+            other @ RESPType::SimpleString(_) | other @ RESPType::BulkString(_) => {
+                let s = String::from_resp(other)?;
+                s.parse::<i64>()
+                    .map_err(|_| Error::InvalidReply(format!("expected integer, got {:?}", s)))
+            }
+            other => Err(invalid("integer", &other)),
+        }
+    }
+}
+
+macro_rules! from_resp_via_i64 {
+    ($($ty:ty),*) => {
+        $(
+            impl FromResp for $ty {
+                fn from_resp(value: RESPType) -> Result<$ty> {
+                    let i = i64::from_resp(value)?;
+                    <$ty>::try_from(i).map_err(|_| {
+                        Error::InvalidReply(format!(
+                            "{} is out of range for {}",
+                            i,
+                            stringify!($ty)
+                        ))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_resp_via_i64!(i32, u32, u64, usize);
+
+impl FromResp for f64 {
+    fn from_resp(value: RESPType) -> Result<f64> {
+        crate::reply::float(&value)
+    }
+}
+
+/// Accepts RESP3 booleans and the integers 0 and 1 that RESP2 uses in
+/// their place.
+impl FromResp for bool {
+    fn from_resp(value: RESPType) -> Result<bool> {
+        match strip(value) {
+            RESPType::Boolean(b) => Ok(b),
+            RESPType::Integer(0) => Ok(false),
+            RESPType::Integer(1) => Ok(true),
+            other => Err(invalid("boolean", &other)),
+        }
+    }
+}
+
+/// Null decodes to `None`.
+impl<T: FromResp> FromResp for Option<T> {
+    fn from_resp(value: RESPType) -> Result<Option<T>> {
+        match strip(value) {
+            RESPType::Null => Ok(None),
+            other => T::from_resp(other).map(Some),
+        }
+    }
+}
+
+fn items(value: RESPType) -> Result<Vec<RESPType>> {
+    match strip(value) {
+        RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => Ok(items),
+        other => Err(invalid("array", &other)),
+    }
+}
+
+impl<T: FromResp> FromResp for Vec<T> {
+    fn from_resp(value: RESPType) -> Result<Vec<T>> {
+        items(value)?.into_iter().map(T::from_resp).collect()
+    }
+}
+
+/// Accepts RESP3 maps and the flat `[key, value, ...]` arrays of RESP2.
+fn entries(value: RESPType) -> Result<Vec<(RESPType, RESPType)>> {
+    match strip(value) {
+        RESPType::Map(entries) => Ok(entries),
+        other => {
+            let items = items(other)?;
+            if items.len() % 2 != 0 {
+                return Err(Error::InvalidReply(format!(
+                    "expected key/value pairs, got {} elements",
+                    items.len()
+                )));
+            }
+            let mut items = items.into_iter();
+            let mut entries = Vec::new();
+            while let (Some(k), Some(v)) = (items.next(), items.next()) {
+                entries.push((k, v));
+            }
+            Ok(entries)
+        }
+    }
+}
+
+impl<K: FromResp + Eq + Hash, V: FromResp> FromResp for HashMap<K, V> {
+    fn from_resp(value: RESPType) -> Result<HashMap<K, V>> {
+        entries(value)?
+            .into_iter()
+            .map(|(k, v)| Ok((K::from_resp(k)?, V::from_resp(v)?)))
+            .collect()
+    }
+}
+
+/// The fields of a map reply or flat key/value array, taken out by name.
+/// This is what `#[derive(FromResp)]` decodes structs with.
+pub struct Fields {
+    entries: Vec<(RESPType, RESPType)>,
+}
+
+impl Fields {
+    pub fn new(value: RESPType) -> Result<Fields> {
+        Ok(Fields {
+            entries: entries(value)?,
+        })
+    }
+
+    /// Removes the field called `name` and decodes it. A missing field
+    /// decodes as null, so that it is accepted for `Option` types only.
+    pub fn take<T: FromResp>(&mut self, name: &str) -> Result<T> {
+        let found = self
+            .entries
+            .iter()
+            .position(|(k, _)| crate::reply::bytes(k).ok() == Some(name.as_bytes()));
+        match found {
+            Some(i) => T::from_resp(self.entries.swap_remove(i).1),
+            None => T::from_resp(RESPType::Null)
+                .map_err(|_| Error::InvalidReply(format!("missing field {:?}", name))),
+        }
+    }
+}
+
+macro_rules! from_resp_tuple {
+    ($len:expr, $($name:ident),+) => {
+        impl<$($name: FromResp),+> FromResp for ($($name,)+) {
+            fn from_resp(value: RESPType) -> Result<($($name,)+)> {
+                let items = items(value)?;
+                if items.len() != $len {
+                    return Err(Error::InvalidReply(format!(
+                        "expected {} elements, got {}",
+                        $len,
+                        items.len()
+                    )));
+                }
+                let mut items = items.into_iter();
+                Ok(($($name::from_resp(items.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+from_resp_tuple!(1, A);
+from_resp_tuple!(2, A, B);
+from_resp_tuple!(3, A, B, C);
+from_resp_tuple!(4, A, B, C, D);
+
+// A blanket TryFrom<RESPType> for every FromResp type would overlap with
+// the standard impl for types with From<RESPType>, such as Option<RESPType>,
+// so the common targets are listed instead.
+macro_rules! try_from_via_from_resp {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<RESPType> for $ty {
+                type Error = Error;
+
+                fn try_from(value: RESPType) -> Result<$ty> {
+                    <$ty>::from_resp(value)
+                }
+            }
+        )*
+    };
+}
+
+try_from_via_from_resp!(
+    String,
+    Vec<u8>,
+    i64,
+    f64,
+    bool,
+    Vec<RESPType>,
+    Vec<String>,
+    Option<String>,
+    Option<Vec<u8>>,
+    Option<i64>
+);
+
+/// A type that can be turned into a value: strings and byte slices become
+/// bulk strings, integers become integers, `None` becomes null, sequences
+/// become arrays and maps become RESP3 maps.
+pub trait ToResp {
+    fn to_resp(&self) -> RESPType;
+}
+
+impl<T: ToResp + ?Sized> ToResp for &T {
+    fn to_resp(&self) -> RESPType {
+        (**self).to_resp()
+    }
+}
+
+impl ToResp for RESPType {
+    fn to_resp(&self) -> RESPType {
+        self.clone()
+    }
+}
+
+impl ToResp for str {
+    fn to_resp(&self) -> RESPType {
+        RESPType::BulkString(self.as_bytes().to_vec())
+    }
+}
+
+impl ToResp for String {
+    fn to_resp(&self) -> RESPType {
+        self.as_str().to_resp()
+    }
+}
+
+impl ToResp for [u8] {
+    fn to_resp(&self) -> RESPType {
+        RESPType::BulkString(self.to_vec())
+    }
+}
+
+impl ToResp for Vec<u8> {
+    fn to_resp(&self) -> RESPType {
+        self.as_slice().to_resp()
+    }
+}
+
+macro_rules! to_resp_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl ToResp for $ty {
+                fn to_resp(&self) -> RESPType {
+                    RESPType::Integer(*self as i64)
+                }
+            }
+        )*
+    };
+}
+
+to_resp_integer!(i32, i64, u32);
+
+/// Values beyond the range of a RESP integer become big numbers.
+impl ToResp for u64 {
+    fn to_resp(&self) -> RESPType {
+        match i64::try_from(*self) {
+            Ok(i) => RESPType::Integer(i),
+            Err(_) => RESPType::BigNumber(self.to_string()),
+        }
+    }
+}
+
+impl ToResp for usize {
+    fn to_resp(&self) -> RESPType {
+        (*self as u64).to_resp()
+    }
+}
+
+impl ToResp for f64 {
+    fn to_resp(&self) -> RESPType {
+        RESPType::Double(*self)
+    }
+}
+
+impl ToResp for bool {
+    fn to_resp(&self) -> RESPType {
+        RESPType::Boolean(*self)
+    }
+}
+
+impl<T: ToResp> ToResp for Option<T> {
+    fn to_resp(&self) -> RESPType {
+        match self {
+            Some(value) => value.to_resp(),
+            None => RESPType::Null,
+        }
+    }
+}
+
+impl<T: ToResp> ToResp for [T] {
+    fn to_resp(&self) -> RESPType {
+        RESPType::Array(self.iter().map(ToResp::to_resp).collect())
+    }
+}
+
+impl<T: ToResp> ToResp for Vec<T> {
+    fn to_resp(&self) -> RESPType {
+        self.as_slice().to_resp()
+    }
+}
+
+impl<K: ToResp, V: ToResp> ToResp for HashMap<K, V> {
+    fn to_resp(&self) -> RESPType {
+        RESPType::Map(
+            self.iter()
+                .map(|(k, v)| (k.to_resp(), v.to_resp()))
+                .collect(),
+        )
+    }
+}
+
+/// Keeps the map's ordering, unlike `HashMap`.
+impl<K: ToResp, V: ToResp> ToResp for BTreeMap<K, V> {
+    fn to_resp(&self) -> RESPType {
+        RESPType::Map(
+            self.iter()
+                .map(|(k, v)| (k.to_resp(), v.to_resp()))
+                .collect(),
+        )
+    }
+}
+
+macro_rules! to_resp_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: ToResp),+> ToResp for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn to_resp(&self) -> RESPType {
+                let ($($name,)+) = self;
+                RESPType::Array(vec![$($name.to_resp()),+])
+            }
+        }
+    };
+}
+
+to_resp_tuple!(A);
+to_resp_tuple!(A, B);
+to_resp_tuple!(A, B, C);
+to_resp_tuple!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+    use std::convert::TryInto;
+
+    #[test]
+    fn converts_scalars() {
+        assert_eq!(bulk("hi").convert::<String>().unwrap(), "hi");
+        assert_eq!(bulk("hi").convert::<Vec<u8>>().unwrap(), b"hi".to_vec());
+        assert_eq!(RESPType::Integer(5).convert::<i64>().unwrap(), 5);
+        assert_eq!(bulk("-3").convert::<i32>().unwrap(), -3);
+        assert!(RESPType::Integer(-1).convert::<u64>().is_err());
+        assert_eq!(bulk("1.5").convert::<f64>().unwrap(), 1.5);
+        assert!(RESPType::Integer(1).convert::<bool>().unwrap());
+        assert!(!RESPType::Boolean(false).convert::<bool>().unwrap());
+        assert_eq!(RESPType::Null.convert::<Option<String>>().unwrap(), None);
+    }
+
+    #[test]
+    fn converts_aggregates() {
+        let reply = RESPType::Array(vec![bulk("a"), RESPType::Null, bulk("c")]);
+        assert_eq!(
+            reply.convert::<Vec<Option<String>>>().unwrap(),
+            vec![Some("a".to_string()), None, Some("c".to_string())]
+        );
+        let flat = RESPType::Array(vec![bulk("x"), RESPType::Integer(1)]);
+        let map = RESPType::Map(vec![(bulk("x"), RESPType::Integer(1))]);
+        let want: HashMap<String, i64> = vec![("x".to_string(), 1)].into_iter().collect();
+        assert_eq!(flat.convert::<HashMap<String, i64>>().unwrap(), want);
+        assert_eq!(map.convert::<HashMap<String, i64>>().unwrap(), want);
+        let pair = RESPType::Array(vec![bulk("k"), RESPType::Integer(2)]);
+        assert_eq!(
+            pair.clone().convert::<(String, i64)>().unwrap(),
+            ("k".to_string(), 2)
+        );
+        assert!(pair.convert::<(String, i64, i64)>().is_err());
+    }
+
+    #[test]
+    fn reports_type_errors() {
+        let attributed = RESPType::Attributed {
+            attrs: vec![],
+            value: Box::new(RESPType::Integer(7)),
+        };
+        assert_eq!(attributed.convert::<i64>().unwrap(), 7);
+        match RESPType::Error("WRONGTYPE Operation".to_string()).convert::<String>() {
+            Err(Error::WrongType(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match RESPType::Null.convert::<Vec<String>>() {
+            Err(Error::TypeMismatch {
+                expected: "array", ..
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn builds_values() {
+        assert_eq!("GET".to_resp(), bulk("GET"));
+        assert_eq!(b"k"[..].to_resp(), bulk("k"));
+        assert_eq!(7i32.to_resp(), RESPType::Integer(7));
+        assert_eq!(
+            u64::MAX.to_resp(),
+            RESPType::BigNumber("18446744073709551615".to_string())
+        );
+        assert_eq!(None::<String>.to_resp(), RESPType::Null);
+        assert_eq!(
+            vec![Some("a"), None].to_resp(),
+            RESPType::Array(vec![bulk("a"), RESPType::Null])
+        );
+        assert_eq!(
+            ("SET", 1.5, true).to_resp(),
+            RESPType::Array(vec![
+                bulk("SET"),
+                RESPType::Double(1.5),
+                RESPType::Boolean(true)
+            ])
+        );
+        let map: BTreeMap<&str, i64> = vec![("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(
+            map.to_resp(),
+            RESPType::Map(vec![
+                (bulk("a"), RESPType::Integer(1)),
+                (bulk("b"), RESPType::Integer(2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trips_through_from_resp() {
+        let value: HashMap<String, Vec<i64>> =
+            vec![("xs".to_string(), vec![1, 2])].into_iter().collect();
+        assert_eq!(
+            value
+                .to_resp()
+                .convert::<HashMap<String, Vec<i64>>>()
+                .unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn supports_try_into() {
+        let n: i64 = RESPType::Integer(3).try_into().unwrap();
+        assert_eq!(n, 3);
+        let s = String::try_from(bulk("x")).unwrap();
+        assert_eq!(s, "x");
+        let missing: Option<Vec<u8>> = RESPType::Null.try_into().unwrap();
+        assert_eq!(missing, None);
+        let err = i64::try_from(bulk("x")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid reply: expected integer, got \"x\""
+        );
+        let err = Vec::<RESPType>::try_from(RESPType::Integer(1)).unwrap_err();
+        assert_eq!(err.to_string(), "Expected array, got integer");
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derives_from_resp() {
+        #[derive(Debug, PartialEq, crate::FromResp)]
+        struct Info {
+            name: String,
+            #[resp(rename = "last-id")]
+            last_id: String,
+            length: i64,
+            r#type: Option<String>,
+            groups: Vec<String>,
+        }
+
+        let reply = RESPType::Map(vec![
+            (bulk("length"), RESPType::Integer(2)),
+            (bulk("name"), bulk("s")),
+            (bulk("last-id"), bulk("1-0")),
+            (bulk("groups"), RESPType::Array(vec![bulk("g")])),
+            (bulk("unknown"), RESPType::Null),
+        ]);
+        assert_eq!(
+            reply.convert::<Info>().unwrap(),
+            Info {
+                name: "s".to_string(),
+                last_id: "1-0".to_string(),
+                length: 2,
+                r#type: None,
+                groups: vec!["g".to_string()],
+            }
+        );
+        let flat = RESPType::Array(vec![bulk("name"), bulk("s")]);
+        match flat.convert::<Info>() {
+            Err(Error::InvalidReply(msg)) => assert!(msg.contains("last-id")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derives_to_resp() {
+        #[derive(crate::ToResp)]
+        struct Reply {
+            name: &'static str,
+            #[resp(rename = "ttl-ms")]
+            ttl: Option<i64>,
+        }
+
+        let reply = Reply {
+            name: "k",
+            ttl: None,
+        };
+        let want = RESPType::Map(vec![
+            (bulk("name"), bulk("k")),
+            (bulk("ttl-ms"), RESPType::Null),
+        ]);
+        assert_eq!(reply.to_resp(), want);
+        assert_eq!(
+            reply.to_resp().to_resp2(),
+            RESPType::Array(vec![
+                bulk("name"),
+                bulk("k"),
+                bulk("ttl-ms"),
+                RESPType::Null
+            ])
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::split::{ScanState, Scanner};
+use crate::{Error, ParseOptions, Parser, RESPType, Result};
+
+/// A push-based decoder that owns no reader. Bytes are handed to
+/// [`Decoder::feed`] as they arrive, split at arbitrary points, and frames
+/// come out once they are complete.
+///
+/// Frame headers are scanned as they arrive and the scan resumes where
+/// the previous call left off, so a large frame fed in small pieces is not
+/// rescanned from its start each time.
+///
+/// After an error the buffered bytes are left in place, so every later
+/// call fails the same way; the connection cannot be resynchronized.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    /// Bytes at the front of `buf` already returned as frames.
+    start: usize,
+    scan: ScanState,
+    options: ParseOptions,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    pub fn with_options(options: ParseOptions) -> Decoder {
+        Decoder {
+            options,
+            ..Decoder::default()
+        }
+    }
+
+    /// Appends `data` to the buffer and returns the first complete frame,
+    /// or `None` if more bytes are needed. When one call completes several
+    /// frames, the rest are returned by later calls, which may pass an
+    /// empty slice.
+    ///
+    /// Fails with [`Error::FrameTooLarge`] once an incomplete frame buffers
+    /// more than [`ParseOptions::max_frame_bytes`].
+    pub fn feed(&mut self, data: &[u8]) -> Result<Option<RESPType>> {
+        if !data.is_empty() && self.start > 0 {
+            self.buf.drain(..self.start);
+            self.scan.pos -= self.start;
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(data);
+        if self.start == self.buf.len() {
+            return Ok(None);
+        }
+        let mut scanner = Scanner::resume(&self.buf, std::mem::take(&mut self.scan), false);
+        scanner.lenient = self.options.integer_plus_sign
+            || self.options.integer_leading_zeros
+            || self.options.integer_whitespace;
+        scanner.max_depth = self.options.max_depth;
+        let result = scanner.skip_frame();
+        self.scan = scanner.suspend();
+        match result {
+            Ok(_) => {}
+            Err(Error::Incomplete { .. }) => {
+                return match self.options.max_frame_bytes {
+                    Some(limit) if self.buffered() as u64 > limit => Err(Error::FrameTooLarge {
+                        limit,
+                        offset: self.start as u64,
+                    }),
+                    _ => Ok(None),
+                }
+            }
+            Err(err) => return Err(err),
+        }
+        let end = self.scan.pos;
+        let frame = &self.buf[self.start..end];
+        let value = match Parser::buffered_with_options(frame, self.options.clone()).parse_next() {
+            Ok(value) => value,
+            Err(err) => {
+                // Scan the frame again next time, so that it fails again.
+                self.scan = ScanState::default();
+                self.scan.pos = self.start;
+                return Err(err);
+            }
+        };
+        self.start = end;
+        Ok(Some(value))
+    }
+
+    /// Number of bytes received but not yet returned as frames.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &[u8] = b"+OK\r\n*2\r\n$3\r\nfoo\r\n%1\r\n+k\r\n:1\r\n$?\r\n;1\r\nx\r\n;0\r\n";
+
+    fn want() -> Vec<RESPType> {
+        vec![
+            RESPType::SimpleString("OK".to_string()),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"foo".to_vec()),
+                RESPType::Map(vec![(
+                    RESPType::SimpleString("k".to_string()),
+                    RESPType::Integer(1),
+                )]),
+            ]),
+            RESPType::BulkString(b"x".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn resumes_across_every_split() {
+        let mut decoder = Decoder::new();
+        let mut have = Vec::new();
+        for &b in STREAM {
+            if let Some(value) = decoder.feed(&[b]).unwrap() {
+                have.push(value);
+            }
+        }
+        assert_eq!(have, want());
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn drains_several_frames_from_one_feed() {
+        let mut decoder = Decoder::new();
+        let mut have = vec![decoder.feed(STREAM).unwrap().unwrap()];
+        while let Some(value) = decoder.feed(&[]).unwrap() {
+            have.push(value);
+        }
+        assert_eq!(have, want());
+    }
+
+    #[test]
+    fn reports_malformed_input() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(b":1").unwrap(), None);
+        assert!(decoder.feed(b"x\r\n").is_err());
+        assert!(decoder.feed(&[]).is_err());
+    }
+
+    #[test]
+    fn decodes_overflowing_integers_as_big_numbers() {
+        let input = b":18446744073709551616\r\n";
+        assert!(Decoder::new().feed(input).is_err());
+        let mut decoder = Decoder::with_options(ParseOptions {
+            integer_overflow_big_number: true,
+            ..ParseOptions::default()
+        });
+        assert_eq!(
+            decoder.feed(input).unwrap(),
+            Some(RESPType::BigNumber("18446744073709551616".to_string()))
+        );
+    }
+
+    #[test]
+    fn resumes_scanning_where_it_stopped() {
+        let mut input = b"*1000\r\n".to_vec();
+        for i in 0..1000 {
+            input.extend(format!(":{}\r\n", i).as_bytes());
+        }
+        let mut decoder = Decoder::new();
+        let mut chunks = input.chunks(7);
+        let mut scanned = 0;
+        let value = loop {
+            if let Some(value) = decoder.feed(chunks.next().unwrap()).unwrap() {
+                break value;
+            }
+            assert!(decoder.scan.pos >= scanned);
+            scanned = decoder.scan.pos;
+        };
+        assert!(scanned > input.len() - 16);
+        assert_eq!(
+            value,
+            RESPType::Array((0..1000).map(RESPType::Integer).collect())
+        );
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn limits_buffered_frame_size() {
+        let mut decoder = Decoder::with_options(ParseOptions {
+            max_frame_bytes: Some(16),
+            ..ParseOptions::default()
+        });
+        assert_eq!(decoder.feed(b"*2\r\n$20\r\n").unwrap(), None);
+        match decoder.feed(b"0123456789") {
+            Err(Error::FrameTooLarge { limit: 16, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn limits_nesting_before_scanning() {
+        let deep = "*1\r\n".repeat(1_000_000);
+        let mut decoder = Decoder::with_options(ParseOptions {
+            max_depth: Some(64),
+            ..ParseOptions::default()
+        });
+        match decoder.feed(deep.as_bytes()) {
+            Err(Error::NestingTooDeep {
+                limit: 64,
+                offset: 256,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn applies_parse_options() {
+        let mut decoder = Decoder::new();
+        assert!(decoder.feed(b":+1\r\n").is_err());
+        let mut decoder = Decoder::with_options(ParseOptions::lenient());
+        assert_eq!(
+            decoder.feed(b":+1\r\n").unwrap(),
+            Some(RESPType::Integer(1))
+        );
+        assert_eq!(
+            decoder.feed(b"*+1\r\n$ 01 \r\nx\r\n").unwrap(),
+            Some(RESPType::Array(vec![RESPType::BulkString(b"x".to_vec())]))
+        );
+        assert!(Decoder::new().feed(b"$+1\r\nx\r\n").is_err());
+    }
+}
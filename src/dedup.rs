@@ -0,0 +1,193 @@
+use crate::encode;
+use crate::RESPType;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct Fnv1a(u64);
+
+impl io::Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RESPType {
+    /// Returns a 64-bit FNV-1a hash of the canonical wire encoding of this
+    /// value: its RESP3 encoding, with `-0.0` written as `0` and every NaN
+    /// as `nan`. The hash is stable across runs, platforms and crate
+    /// versions, so it can be persisted, and values that compare equal hash
+    /// alike.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a(FNV_OFFSET);
+        encode::write_value(&mut hasher, &canonical(self)).expect("hashing cannot fail");
+        hasher.0
+    }
+}
+
+/// Collapses the doubles that compare equal but encode differently.
+fn canonical(value: &RESPType) -> RESPType {
+    let items = |items: &[RESPType]| items.iter().map(canonical).collect();
+    let entries = |entries: &[(RESPType, RESPType)]| {
+        entries
+            .iter()
+            .map(|(k, v)| (canonical(k), canonical(v)))
+            .collect()
+    };
+    match value {
+        RESPType::Double(d) if d.is_nan() => RESPType::Double(f64::NAN),
+        RESPType::Double(d) if *d == 0.0 => RESPType::Double(0.0),
+        RESPType::Array(v) => RESPType::Array(items(v)),
+        RESPType::Set(v) => RESPType::Set(items(v)),
+        RESPType::Push(v) => RESPType::Push(items(v)),
+        RESPType::Map(v) => RESPType::Map(entries(v)),
+        RESPType::Attributed { attrs, value } => RESPType::Attributed {
+            attrs: entries(attrs),
+            value: Box::new(canonical(value)),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Counts occurrences of distinct values, keeping the first instance of
+/// each. Values are compared with `==`.
+#[derive(Debug, Default)]
+pub struct Dedup {
+    entries: HashMap<RESPType, usize>,
+}
+
+impl Dedup {
+    pub fn new() -> Dedup {
+        Dedup::default()
+    }
+
+    /// Records an occurrence of `value` and returns how many times it has
+    /// been inserted, this one included.
+    pub fn insert(&mut self, value: RESPType) -> usize {
+        let count = self.entries.entry(value).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Number of times a value equal to `value` has been inserted.
+    pub fn count(&self, value: &RESPType) -> usize {
+        self.entries.get(value).copied().unwrap_or(0)
+    }
+
+    /// The first instance inserted of a value equal to `value`.
+    pub fn get(&self, value: &RESPType) -> Option<&RESPType> {
+        self.entries.get_key_value(value).map(|(first, _)| first)
+    }
+
+    /// Number of distinct values seen.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns up to `n` of the most frequent values, most frequent first.
+    /// Ties are broken by wire encoding so that the order is deterministic.
+    pub fn top(&self, n: usize) -> Vec<(&RESPType, usize)> {
+        let mut all: Vec<(&RESPType, usize)> = self
+            .entries
+            .iter()
+            .map(|(value, count)| (value, *count))
+            .collect();
+        all.sort_by_cached_key(|&(value, count)| (Reverse(count), encode::to_vec_unchecked(value)));
+        all.truncate(n);
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(RESPType::Integer(1).content_hash(), 0xfa15_374d_4532_1e03);
+        assert_eq!(
+            RESPType::SimpleString("OK".to_string()).content_hash(),
+            RESPType::SimpleString("OK".to_string()).content_hash()
+        );
+    }
+
+    #[test]
+    fn content_hash_distinguishes_types() {
+        let simple = RESPType::SimpleString("OK".to_string());
+        let bulk = RESPType::BulkString(b"OK".to_vec());
+        assert_ne!(simple.content_hash(), bulk.content_hash());
+    }
+
+    #[test]
+    fn equal_values_hash_alike() {
+        let pairs = [
+            (RESPType::Double(0.0), RESPType::Double(-0.0)),
+            (
+                RESPType::Double(f64::NAN),
+                RESPType::Double(f64::from_bits(f64::NAN.to_bits() | 1)),
+            ),
+            (
+                RESPType::Map(vec![(RESPType::Null, RESPType::Double(-0.0))]),
+                RESPType::Map(vec![(RESPType::Null, RESPType::Double(0.0))]),
+            ),
+        ];
+        for (a, b) in pairs.iter() {
+            assert_eq!(a, b);
+            assert_eq!(a.content_hash(), b.content_hash());
+        }
+    }
+
+    #[test]
+    fn dedup_counts_and_ranks() {
+        let mut dedup = Dedup::new();
+        let ok = RESPType::SimpleString("OK".to_string());
+        assert_eq!(dedup.insert(ok.clone()), 1);
+        dedup.insert(RESPType::Integer(1));
+        assert_eq!(dedup.insert(ok.clone()), 2);
+        dedup.insert(RESPType::Null);
+        dedup.insert(ok.clone());
+        dedup.insert(RESPType::Null);
+
+        assert_eq!(dedup.len(), 3);
+        assert_eq!(dedup.count(&ok), 3);
+        assert_eq!(dedup.count(&RESPType::Boolean(true)), 0);
+        assert_eq!(dedup.get(&ok), Some(&ok));
+
+        let top = dedup.top(2);
+        assert_eq!(
+            top,
+            vec![
+                (&RESPType::SimpleString("OK".to_string()), 3),
+                (&RESPType::Null, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn dedup_keys_by_value() {
+        let mut dedup = Dedup::new();
+        dedup.insert(RESPType::Double(0.0));
+        dedup.insert(RESPType::Double(-0.0));
+        assert_eq!(dedup.len(), 1);
+        assert_eq!(dedup.count(&RESPType::Double(-0.0)), 2);
+        match dedup.get(&RESPType::Double(-0.0)) {
+            Some(RESPType::Double(d)) => assert!(d.is_sign_positive()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::escape::Escaped;
+use crate::RESPType;
+use std::fmt;
+
+/// A single difference found by [`diff`], located by the chain of
+/// aggregate indices leading to it from the root. Within a map, the key of
+/// entry `i` is at index `2 * i` and its value at `2 * i + 1`. Within an
+/// attributed value, index 0 is the attribute map and index 1 the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub path: Vec<usize>,
+    pub kind: DiffKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffKind {
+    /// The two values are of different RESP types.
+    TypeMismatch {
+        left: &'static str,
+        right: &'static str,
+    },
+    /// Both values have the same type but different contents. The contents
+    /// are rendered in escaped form.
+    ValueMismatch { left: String, right: String },
+    /// Both values are aggregates of different lengths, counted in elements
+    /// or map entries. Elements present in both are still compared.
+    LengthMismatch { left: usize, right: usize },
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("$")?;
+        for i in &self.path {
+            f.write_fmt(format_args!("[{}]", i))?;
+        }
+        match &self.kind {
+            DiffKind::TypeMismatch { left, right } => {
+                f.write_fmt(format_args!(": type {} != {}", left, right))
+            }
+            DiffKind::ValueMismatch { left, right } => {
+                f.write_fmt(format_args!(": value {} != {}", left, right))
+            }
+            DiffKind::LengthMismatch { left, right } => {
+                f.write_fmt(format_args!(": length {} != {}", left, right))
+            }
+        }
+    }
+}
+
+/// Compares two values structurally and returns every difference found.
+/// An empty result means the values are equal.
+pub fn diff(left: &RESPType, right: &RESPType) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    let mut path = Vec::new();
+    diff_into(left, right, &mut path, &mut entries);
+    entries
+}
+
+/// Like [`diff`], but compares the [`RESPType::normalized`] forms of the
+/// values, so that differences in encoding alone are not reported.
+pub fn diff_normalized(left: &RESPType, right: &RESPType) -> Vec<DiffEntry> {
+    diff(&left.normalized(), &right.normalized())
+}
+
+fn diff_into(
+    left: &RESPType,
+    right: &RESPType,
+    path: &mut Vec<usize>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    let mut push = |kind| {
+        entries.push(DiffEntry {
+            path: path.clone(),
+            kind,
+        })
+    };
+    match (left, right) {
+        (RESPType::SimpleString(l), RESPType::SimpleString(r))
+        | (RESPType::Error(l), RESPType::Error(r)) => {
+            if l != r {
+                push(value_mismatch(l.as_bytes(), r.as_bytes()));
+            }
+        }
+        (RESPType::BulkString(l), RESPType::BulkString(r))
+        | (RESPType::BulkError(l), RESPType::BulkError(r)) => {
+            if l != r {
+                push(value_mismatch(l, r));
+            }
+        }
+        (RESPType::Integer(l), RESPType::Integer(r)) => {
+            if l != r {
+                push(DiffKind::ValueMismatch {
+                    left: l.to_string(),
+                    right: r.to_string(),
+                });
+            }
+        }
+        (
+            RESPType::Verbatim {
+                format: lf,
+                text: lt,
+            },
+            RESPType::Verbatim {
+                format: rf,
+                text: rt,
+            },
+        ) => {
+            if lf != rf || lt != rt {
+                push(value_mismatch(
+                    &[&lf[..], b":", lt].concat(),
+                    &[&rf[..], b":", rt].concat(),
+                ));
+            }
+        }
+        (RESPType::Double(l), RESPType::Double(r)) => {
+            if l != r && !(l.is_nan() && r.is_nan()) {
+                push(DiffKind::ValueMismatch {
+                    left: l.to_string(),
+                    right: r.to_string(),
+                });
+            }
+        }
+        (RESPType::Boolean(l), RESPType::Boolean(r)) => {
+            if l != r {
+                push(DiffKind::ValueMismatch {
+                    left: l.to_string(),
+                    right: r.to_string(),
+                });
+            }
+        }
+        (RESPType::BigNumber(l), RESPType::BigNumber(r)) => {
+            if l != r {
+                push(DiffKind::ValueMismatch {
+                    left: l.clone(),
+                    right: r.clone(),
+                });
+            }
+        }
+        (RESPType::Extension(l), RESPType::Extension(r)) if l.tag == r.tag => {
+            if l.payload != r.payload {
+                push(value_mismatch(&l.payload, &r.payload));
+            }
+        }
+        (RESPType::Null, RESPType::Null) => {}
+        (RESPType::Map(l), RESPType::Map(r)) => diff_entries(l, r, path, entries),
+        (
+            RESPType::Attributed {
+                attrs: la,
+                value: lv,
+            },
+            RESPType::Attributed {
+                attrs: ra,
+                value: rv,
+            },
+        ) => {
+            path.push(0);
+            diff_entries(la, ra, path, entries);
+            path.pop();
+            path.push(1);
+            diff_into(lv, rv, path, entries);
+            path.pop();
+        }
+        (RESPType::Array(l), RESPType::Array(r))
+        | (RESPType::Set(l), RESPType::Set(r))
+        | (RESPType::Push(l), RESPType::Push(r)) => {
+            if l.len() != r.len() {
+                push(DiffKind::LengthMismatch {
+                    left: l.len(),
+                    right: r.len(),
+                });
+            }
+            for (i, (l, r)) in l.iter().zip(r.iter()).enumerate() {
+                path.push(i);
+                diff_into(l, r, path, entries);
+                path.pop();
+            }
+        }
+        (l, r) => push(DiffKind::TypeMismatch {
+            left: l.kind().name(),
+            right: r.kind().name(),
+        }),
+    }
+}
+
+fn diff_entries(
+    left: &[(RESPType, RESPType)],
+    right: &[(RESPType, RESPType)],
+    path: &mut Vec<usize>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    if left.len() != right.len() {
+        entries.push(DiffEntry {
+            path: path.clone(),
+            kind: DiffKind::LengthMismatch {
+                left: left.len(),
+                right: right.len(),
+            },
+        });
+    }
+    for (i, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+        path.push(2 * i);
+        diff_into(&l.0, &r.0, path, entries);
+        path.pop();
+        path.push(2 * i + 1);
+        diff_into(&l.1, &r.1, path, entries);
+        path.pop();
+    }
+}
+
+fn value_mismatch(left: &[u8], right: &[u8]) -> DiffKind {
+    DiffKind::ValueMismatch {
+        left: Escaped::new(left).to_string(),
+        right: Escaped::new(right).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_values_have_no_diff() {
+        let v = RESPType::Array(vec![RESPType::Integer(1), RESPType::Null]);
+        let w = RESPType::Array(vec![RESPType::Integer(1), RESPType::Null]);
+        assert!(diff(&v, &w).is_empty());
+    }
+
+    #[test]
+    fn reports_type_mismatch() {
+        let have = diff(&RESPType::Integer(1), &RESPType::Null);
+        assert_eq!(
+            have,
+            vec![DiffEntry {
+                path: vec![],
+                kind: DiffKind::TypeMismatch {
+                    left: "integer",
+                    right: "null"
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_nested_value_and_length_mismatches() {
+        let left = RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::Array(vec![RESPType::BulkString(b"a".to_vec())]),
+        ]);
+        let right = RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::Array(vec![RESPType::BulkString(b"b".to_vec()), RESPType::Null]),
+        ]);
+        let have: Vec<String> = diff(&left, &right).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            have,
+            vec![
+                "$[1]: length 1 != 2".to_string(),
+                "$[1][0]: value \"a\" != \"b\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_diff_ignores_string_flavour() {
+        let left = RESPType::SimpleString("OK".to_string());
+        let right = RESPType::BulkString(b"OK".to_vec());
+        assert_eq!(diff(&left, &right).len(), 1);
+        assert!(diff_normalized(&left, &right).is_empty());
+    }
+
+    #[test]
+    fn locates_map_keys_and_values() {
+        let left = RESPType::Map(vec![
+            (RESPType::Integer(1), RESPType::Integer(2)),
+            (RESPType::Integer(3), RESPType::Integer(4)),
+        ]);
+        let right = RESPType::Map(vec![
+            (RESPType::Integer(1), RESPType::Integer(2)),
+            (RESPType::Integer(5), RESPType::Integer(4)),
+        ]);
+        let have: Vec<String> = diff(&left, &right).iter().map(|d| d.to_string()).collect();
+        assert_eq!(have, vec!["$[2]: value 3 != 5".to_string()]);
+        let set = RESPType::Set(vec![]);
+        assert_eq!(diff(&set, &RESPType::Array(vec![])).len(), 1);
+    }
+}
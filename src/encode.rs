@@ -0,0 +1,379 @@
+//! Conversion of values to their wire encoding.
+
+use crate::extension::Framing;
+use crate::{Error, RESPType, Result};
+use std::io::{self, Write};
+
+/// Returns the wire encoding of `value`. Fails with
+/// [`Error::InvalidLine`] if a line-framed payload contains `\r` or `\n`.
+pub fn to_vec(value: &RESPType) -> Result<Vec<u8>> {
+    check_lines(value)?;
+    Ok(to_vec_unchecked(value))
+}
+
+/// Writes the wire encoding of `value` to `writer`. Nothing is written if
+/// the value cannot be encoded; see [`to_vec`].
+pub fn to_writer<W: io::Write>(mut writer: W, value: &RESPType) -> Result<()> {
+    check_lines(value)?;
+    Ok(write_value(&mut writer, value)?)
+}
+
+/// Encodes `value` without checking its lines, for callers that only use
+/// the bytes as a key.
+pub(crate) fn to_vec_unchecked(value: &RESPType) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_value(&mut buf, value).expect("writing to a Vec cannot fail");
+    buf
+}
+
+/// Rejects simple strings, errors, big numbers and line extensions that
+/// hold a line break, since writing one would end the frame early and let
+/// the rest of the payload be read as further frames.
+fn check_lines(value: &RESPType) -> Result<()> {
+    let line: &[u8] = match value {
+        RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => s.as_bytes(),
+        RESPType::Extension(ext) if ext.framing == Framing::Line => &ext.payload,
+        RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => {
+            return items.iter().try_for_each(check_lines)
+        }
+        RESPType::Map(entries) => {
+            return entries
+                .iter()
+                .try_for_each(|(k, v)| check_lines(k).and_then(|_| check_lines(v)))
+        }
+        RESPType::Attributed { attrs, value } => {
+            for (k, v) in attrs {
+                check_lines(k)?;
+                check_lines(v)?;
+            }
+            return check_lines(value);
+        }
+        _ => return Ok(()),
+    };
+    if line.iter().any(|&b| b == b'\r' || b == b'\n') {
+        return Err(Error::InvalidLine(
+            String::from_utf8_lossy(line).into_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Encodes a command as the array of bulk strings that clients send, e.g.
+/// `encode_command(&["SET", "k", "v"])`.
+pub fn encode_command<I>(args: I) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut buf = Vec::new();
+    write_command(&mut buf, args).expect("writing to a Vec cannot fail");
+    buf
+}
+
+fn write_command<W, I>(w: &mut W, args: I) -> io::Result<()>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let args: Vec<I::Item> = args.into_iter().collect();
+    write!(w, "*{}\r\n", args.len())?;
+    for arg in &args {
+        let arg = arg.as_ref();
+        write!(w, "${}\r\n", arg.len())?;
+        w.write_all(arg)?;
+        w.write_all(b"\r\n")?;
+    }
+    Ok(())
+}
+
+/// Writes values to an underlying writer as they are encoded, without
+/// building intermediate buffers. Writes are not buffered, so wrap the
+/// writer in an [`io::BufWriter`] when encoding many small values.
+pub struct Encoder<W: io::Write> {
+    out: Counted<W>,
+}
+
+impl<W: io::Write> Encoder<W> {
+    pub fn new(writer: W) -> Encoder<W> {
+        Encoder {
+            out: Counted {
+                inner: writer,
+                count: 0,
+            },
+        }
+    }
+
+    /// Writes a value, or nothing if it cannot be encoded; see [`to_vec`].
+    pub fn encode(&mut self, value: &RESPType) -> Result<()> {
+        check_lines(value)?;
+        Ok(write_value(&mut self.out, value)?)
+    }
+
+    /// Writes a command; see [`encode_command`].
+    pub fn encode_command<I>(&mut self, args: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        Ok(write_command(&mut self.out, args)?)
+    }
+
+    /// Writes a bulk string of `len` bytes copied from `reader`, so that
+    /// large payloads never have to be held in memory. Fails with
+    /// [`Error::EndOfStream`] if the reader ends early, in which case the
+    /// output holds a truncated frame.
+    pub fn encode_bulk_from<R: io::Read>(&mut self, len: u64, reader: R) -> Result<()> {
+        write!(self.out, "${}\r\n", len)?;
+        let copied = io::copy(&mut reader.take(len), &mut self.out)?;
+        if copied < len {
+            return Err(Error::EndOfStream);
+        }
+        self.out.write_all(b"\r\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    /// Number of bytes written so far.
+    pub fn offset(&self) -> u64 {
+        self.out.count
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out.inner
+    }
+}
+
+struct Counted<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: io::Write> io::Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) fn write_value<W: io::Write>(w: &mut W, value: &RESPType) -> io::Result<()> {
+    match value {
+        RESPType::SimpleString(s) => write_line(w, b'+', s.as_bytes()),
+        RESPType::Error(s) => write_line(w, b'-', s.as_bytes()),
+        RESPType::Integer(i) => write!(w, ":{}\r\n", i),
+        RESPType::BulkString(buf) => write_blob(w, b'$', buf),
+        RESPType::BulkError(buf) => write_blob(w, b'!', buf),
+        RESPType::Null => w.write_all(b"$-1\r\n"),
+        RESPType::Array(items) => write_items(w, b'*', items),
+        RESPType::Map(entries) => write_entries(w, b'%', entries),
+        RESPType::Attributed { attrs, value } => {
+            write_entries(w, b'|', attrs)?;
+            write_value(w, value)
+        }
+        RESPType::Set(items) => write_items(w, b'~', items),
+        RESPType::Push(items) => write_items(w, b'>', items),
+        RESPType::Double(d) => {
+            if d.is_nan() {
+                w.write_all(b",nan\r\n")
+            } else {
+                write!(w, ",{}\r\n", d)
+            }
+        }
+        RESPType::Boolean(b) => w.write_all(if *b { b"#t\r\n" } else { b"#f\r\n" }),
+        RESPType::BigNumber(n) => write_line(w, b'(', n.as_bytes()),
+        RESPType::Verbatim { format, text } => {
+            write!(w, "={}\r\n", text.len() + 4)?;
+            w.write_all(format)?;
+            w.write_all(b":")?;
+            w.write_all(text)?;
+            w.write_all(b"\r\n")
+        }
+        RESPType::Extension(ext) => match ext.framing {
+            Framing::Line => write_line(w, ext.tag, &ext.payload),
+            Framing::Blob => {
+                w.write_all(&[ext.tag])?;
+                write!(w, "{}\r\n", ext.payload.len())?;
+                w.write_all(&ext.payload)?;
+                w.write_all(b"\r\n")
+            }
+        },
+    }
+}
+
+fn write_items<W: io::Write>(w: &mut W, marker: u8, items: &[RESPType]) -> io::Result<()> {
+    write!(w, "{}{}\r\n", marker as char, items.len())?;
+    for item in items {
+        write_value(w, item)?;
+    }
+    Ok(())
+}
+
+fn write_entries<W: io::Write>(
+    w: &mut W,
+    marker: u8,
+    entries: &[(RESPType, RESPType)],
+) -> io::Result<()> {
+    write!(w, "{}{}\r\n", marker as char, entries.len())?;
+    for (key, value) in entries {
+        write_value(w, key)?;
+        write_value(w, value)?;
+    }
+    Ok(())
+}
+
+fn write_blob<W: io::Write>(w: &mut W, marker: u8, payload: &[u8]) -> io::Result<()> {
+    write!(w, "{}{}\r\n", marker as char, payload.len())?;
+    w.write_all(payload)?;
+    w.write_all(b"\r\n")
+}
+
+fn write_line<W: io::Write>(w: &mut W, marker: u8, line: &[u8]) -> io::Result<()> {
+    w.write_all(&[marker])?;
+    w.write_all(line)?;
+    w.write_all(b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_slice;
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let value = RESPType::Array(vec![
+            RESPType::SimpleString("OK".to_string()),
+            RESPType::BulkString(b"a\r\nb".to_vec()),
+            RESPType::Null,
+        ]);
+        let bytes = to_vec(&value).unwrap();
+        assert_eq!(bytes, b"*3\r\n+OK\r\n$4\r\na\r\nb\r\n$-1\r\n".to_vec());
+        assert_eq!(from_slice(&bytes).unwrap(), value);
+
+        let mut sink = Vec::new();
+        to_writer(&mut sink, &value).unwrap();
+        assert_eq!(sink, bytes);
+    }
+
+    #[test]
+    fn encodes_every_variant() {
+        let value = RESPType::Array(vec![
+            RESPType::Error("ERR bad".to_string()),
+            RESPType::Integer(-3),
+            RESPType::Array(vec![RESPType::BulkString(Vec::new())]),
+            RESPType::Array(Vec::new()),
+            RESPType::Extension(crate::Extension {
+                tag: b'@',
+                framing: Framing::Line,
+                payload: b"x".to_vec(),
+            }),
+            RESPType::Extension(crate::Extension {
+                tag: b'^',
+                framing: Framing::Blob,
+                payload: b"yz".to_vec(),
+            }),
+        ]);
+        assert_eq!(
+            to_vec(&value).unwrap(),
+            b"*6\r\n-ERR bad\r\n:-3\r\n*1\r\n$0\r\n\r\n*0\r\n@x\r\n^2\r\nyz\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn encoder_streams_values() {
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.encode(&RESPType::Integer(1)).unwrap();
+        encoder.encode_bulk_from(3, &b"abcdef"[..]).unwrap();
+        assert_eq!(encoder.offset(), 13);
+        assert!(encoder.encode_bulk_from(5, &b"ab"[..]).is_err());
+        let out = encoder.into_inner();
+        assert!(out.starts_with(b":1\r\n$3\r\nabc\r\n$5\r\nab"));
+        assert!(crate::parse_exact_n(&out[..13], 2).is_ok());
+    }
+
+    #[test]
+    fn rejects_line_breaks_in_lines() {
+        let injected = RESPType::Error("x\r\n+OK".to_string());
+        match to_vec(&injected) {
+            Err(Error::InvalidLine(s)) => assert_eq!(s, "x\r\n+OK"),
+            other => panic!("unexpected {:?}", other),
+        }
+        let nested = RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::SimpleString("a\nb".to_string()),
+        ]);
+        assert!(to_vec(&nested).is_err());
+        assert!(to_vec(&RESPType::BigNumber("1\r".to_string())).is_err());
+
+        let mut encoder = Encoder::new(Vec::new());
+        assert!(encoder.encode(&nested).is_err());
+        assert_eq!(encoder.offset(), 0);
+        let mut out = Vec::new();
+        assert!(to_writer(&mut out, &injected).is_err());
+        assert!(out.is_empty());
+        // Blob payloads may hold anything.
+        assert!(to_vec(&RESPType::BulkError(b"x\r\n+OK".to_vec())).is_ok());
+    }
+
+    #[test]
+    fn encodes_commands() {
+        assert_eq!(
+            encode_command(&["SET", "k", "v"]),
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n".to_vec()
+        );
+        let args: Vec<Vec<u8>> = vec![b"GET".to_vec(), b"\r\n".to_vec()];
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.encode_command(&args).unwrap();
+        assert_eq!(
+            from_slice(&encoder.into_inner()).unwrap(),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"GET".to_vec()),
+                RESPType::BulkString(b"\r\n".to_vec()),
+            ])
+        );
+        assert_eq!(encode_command(Vec::<&str>::new()), b"*0\r\n".to_vec());
+    }
+
+    #[test]
+    fn encodes_resp3_types() {
+        let value = RESPType::Array(vec![
+            RESPType::Double(1.5),
+            RESPType::Double(f64::INFINITY),
+            RESPType::Double(f64::NAN),
+            RESPType::Boolean(true),
+            RESPType::BigNumber("12345678901234567890".to_string()),
+            RESPType::Attributed {
+                attrs: vec![(RESPType::Integer(1), RESPType::Integer(2))],
+                value: Box::new(RESPType::Set(vec![])),
+            },
+            RESPType::Push(vec![RESPType::Integer(3)]),
+            RESPType::Verbatim {
+                format: *b"txt",
+                text: b"hi".to_vec(),
+            },
+            RESPType::BulkError(b"ERR\n".to_vec()),
+        ]);
+        let bytes = to_vec(&value).unwrap();
+        assert_eq!(
+            bytes,
+            b"*9\r\n,1.5\r\n,inf\r\n,nan\r\n#t\r\n(12345678901234567890\r\n|1\r\n:1\r\n:2\r\n~0\r\n>1\r\n:3\r\n=6\r\ntxt:hi\r\n!4\r\nERR\n\r\n"
+                .to_vec()
+        );
+        assert_eq!(to_vec(&from_slice(&bytes).unwrap()).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::RESPKind;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An integer that does not fit in an `i64`, on the line starting at
+    /// `offset`.
+    BadInteger {
+        offset: u64,
+        source: std::num::ParseIntError,
+    },
+    /// A line that is not valid UTF-8, starting at `offset`.
+    BadString {
+        offset: u64,
+        source: std::string::FromUtf8Error,
+    },
+    EndOfStream,
+    /// The frame starting at `offset` is longer than `limit` bytes.
+    FrameTooLarge {
+        limit: u64,
+        offset: u64,
+    },
+    /// The input ends partway through a frame, so more bytes have to be
+    /// read before it can be parsed. `needed` is how many more, when that
+    /// is known.
+    Incomplete {
+        needed: Option<usize>,
+    },
+    /// A length header that is negative, other than the `-1` of a RESP2
+    /// null, on the line starting at `offset`.
+    InvalidLength {
+        length: i64,
+        offset: u64,
+    },
+    /// A line-framed payload, such as a simple string or error, that holds
+    /// `\r` or `\n` and so cannot be encoded.
+    InvalidLine(String),
+    /// A request that is not a well-formed command.
+    InvalidCommand(String),
+    InvalidReply(String),
+    IoError(io::Error),
+    /// A payload that does not parse as its type, starting at `offset`.
+    MalformedBoolean {
+        value: String,
+        offset: u64,
+    },
+    MalformedDouble {
+        value: String,
+        offset: u64,
+    },
+    MalformedInteger {
+        value: String,
+        offset: u64,
+    },
+    MalformedVerbatim {
+        value: String,
+        offset: u64,
+    },
+    /// Aggregates are nested deeper than the configured limit, at the
+    /// header starting at `offset`.
+    NestingTooDeep {
+        limit: usize,
+        offset: u64,
+    },
+    ReservedTypeByte(u8),
+    /// `len` bytes of input left over after the frames wanted, starting at
+    /// `offset`.
+    TrailingBytes {
+        len: usize,
+        offset: u64,
+    },
+    /// A value of the wrong type where a typed reply was expected.
+    TypeMismatch {
+        expected: &'static str,
+        actual: RESPKind,
+    },
+    /// An inline command with a quoted word that is not closed, or whose
+    /// closing quote is not followed by a space.
+    UnbalancedQuotes,
+    /// A byte that is not valid where it appears, at `offset` in the input.
+    UnexpectedToken {
+        byte: u8,
+        offset: u64,
+    },
+    UnknownError,
+    /// The server answered with a `WRONGTYPE` error instead of a value.
+    WrongType(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::BadInteger { offset, source } => {
+                f.write_fmt(format_args!("Bad integer at offset {}: {}", offset, source))
+            }
+            Error::BadString { offset, source } => {
+                f.write_fmt(format_args!("Bad string at offset {}: {}", offset, source))
+            }
+            Error::EndOfStream => f.write_str("End of stream"),
+            Error::Incomplete { needed: None } => f.write_str("Incomplete frame"),
+            Error::Incomplete { needed: Some(n) } => {
+                f.write_fmt(format_args!("Incomplete frame, {} more bytes needed", n))
+            }
+            Error::UnexpectedToken { byte, offset } => f.write_fmt(format_args!(
+                "Unexpected token {:?} at offset {}",
+                *byte as char, offset
+            )),
+            Error::InvalidLength { length, offset } => f.write_fmt(format_args!(
+                "Invalid length {} at offset {}",
+                length, offset
+            )),
+            Error::InvalidCommand(msg) => f.write_fmt(format_args!("Invalid command: {}", msg)),
+            Error::InvalidLine(s) => f.write_fmt(format_args!("Invalid line: {:?}", s)),
+            Error::InvalidReply(msg) => f.write_fmt(format_args!("Invalid reply: {}", msg)),
+            Error::IoError(err) => f.write_fmt(format_args!("IO error: {}", err)),
+            Error::MalformedBoolean { value, offset } => f.write_fmt(format_args!(
+                "Malformed boolean {:?} at offset {}",
+                value, offset
+            )),
+            Error::MalformedDouble { value, offset } => f.write_fmt(format_args!(
+                "Malformed double {:?} at offset {}",
+                value, offset
+            )),
+            Error::MalformedInteger { value, offset } => f.write_fmt(format_args!(
+                "Malformed integer {:?} at offset {}",
+                value, offset
+            )),
+            Error::MalformedVerbatim { value, offset } => f.write_fmt(format_args!(
+                "Malformed verbatim string {:?} at offset {}",
+                value, offset
+            )),
+            Error::FrameTooLarge { limit, offset } => f.write_fmt(format_args!(
+                "Frame at offset {} exceeds {} bytes",
+                offset, limit
+            )),
+            Error::NestingTooDeep { limit, offset } => f.write_fmt(format_args!(
+                "Nesting exceeds {} levels at offset {}",
+                limit, offset
+            )),
+            Error::ReservedTypeByte(b) => {
+                f.write_fmt(format_args!("Reserved type byte: {:?}", *b as char))
+            }
+            Error::TrailingBytes { len, offset } => f.write_fmt(format_args!(
+                "{} unexpected trailing bytes at offset {}",
+                len, offset
+            )),
+            Error::TypeMismatch { expected, actual } => {
+                f.write_fmt(format_args!("Expected {}, got {}", expected, actual))
+            }
+            Error::UnbalancedQuotes => f.write_str("Unbalanced quotes in inline command"),
+            Error::UnknownError => f.write_str("Unknown error"),
+            Error::WrongType(msg) => f.write_fmt(format_args!("Wrong type: {}", msg)),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        "description"
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::IoError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,178 @@
+use crate::encode;
+use crate::RESPType;
+use std::fmt;
+
+/// Displays a byte payload as a quoted, binary-safe string.
+///
+/// Printable ASCII is written as-is, `\r`, `\n`, `\t`, `\\` and `"` use their
+/// usual escapes and every other byte is written as `\xNN`. Payloads longer
+/// than the configured hexdump threshold are rendered as a hexdump block
+/// instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Escaped<'a> {
+    bytes: &'a [u8],
+    hexdump_threshold: Option<usize>,
+}
+
+impl<'a> Escaped<'a> {
+    pub fn new(bytes: &'a [u8]) -> Escaped<'a> {
+        Escaped {
+            bytes,
+            hexdump_threshold: None,
+        }
+    }
+
+    /// Render payloads longer than `len` bytes as a hexdump.
+    pub fn hexdump_above(mut self, len: usize) -> Escaped<'a> {
+        self.hexdump_threshold = Some(len);
+        self
+    }
+}
+
+impl<'a> fmt::Display for Escaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.hexdump_threshold {
+            Some(n) if self.bytes.len() > n => HexDump(self.bytes).fmt(f),
+            _ => {
+                f.write_str("\"")?;
+                write_escaped(f, self.bytes)?;
+                f.write_str("\"")
+            }
+        }
+    }
+}
+
+pub(crate) fn write_escaped<W: fmt::Write>(w: &mut W, bytes: &[u8]) -> fmt::Result {
+    for &b in bytes {
+        match b {
+            b'\r' => w.write_str("\\r")?,
+            b'\n' => w.write_str("\\n")?,
+            b'\t' => w.write_str("\\t")?,
+            b'\\' => w.write_str("\\\\")?,
+            b'"' => w.write_str("\\\"")?,
+            0x20..=0x7e => w.write_char(b as char)?,
+            _ => w.write_fmt(format_args!("\\x{:02x}", b))?,
+        }
+    }
+    Ok(())
+}
+
+/// Displays a byte payload as a classic 16-bytes-per-line hexdump with an
+/// offset column and an ASCII gutter.
+#[derive(Debug, Clone, Copy)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, chunk) in self.0.chunks(16).enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_fmt(format_args!("{:08x} ", i * 16))?;
+            for j in 0..16 {
+                if j == 8 {
+                    f.write_str(" ")?;
+                }
+                match chunk.get(j) {
+                    Some(b) => f.write_fmt(format_args!(" {:02x}", b))?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str("  |")?;
+            for &b in chunk {
+                let c = if (0x20..=0x7e).contains(&b) {
+                    b as char
+                } else {
+                    '.'
+                };
+                f.write_fmt(format_args!("{}", c))?;
+            }
+            f.write_str("|")?;
+        }
+        Ok(())
+    }
+}
+
+/// How [`RESPType::to_debug_wire_string_with`] lays out the escaped frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireStyle {
+    /// Everything on one line, e.g. `*1\r\n:1\r\n`.
+    SingleLine,
+    /// A real line break after every escaped `\r\n`, which reads better
+    /// for large frames.
+    Multiline,
+}
+
+impl RESPType {
+    /// Encodes this value and escapes the result, so that it can be pasted
+    /// into a Rust string literal or shown in a log line. For example, an
+    /// array holding the integer 1 renders as `*1\r\n:1\r\n`.
+    pub fn to_debug_wire_string(&self) -> String {
+        self.to_debug_wire_string_with(WireStyle::SingleLine)
+    }
+
+    pub fn to_debug_wire_string_with(&self, style: WireStyle) -> String {
+        let mut wire = Vec::new();
+        encode::write_value(&mut wire, self).expect("writing to a Vec cannot fail");
+        let mut out = String::with_capacity(wire.len() * 2);
+        for (i, chunk) in wire.split_inclusive(|&b| b == b'\n').enumerate() {
+            if i > 0 && style == WireStyle::Multiline {
+                out.push('\n');
+            }
+            write_escaped(&mut out, chunk).expect("writing to a String cannot fail");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_control_and_high_bytes() {
+        let have = Escaped::new(b"a\r\n\t\"\\\x00\xff~").to_string();
+        assert_eq!(have, "\"a\\r\\n\\t\\\"\\\\\\x00\\xff~\"");
+    }
+
+    #[test]
+    fn debug_wire_string() {
+        let value = RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::BulkString(b"a\x00".to_vec()),
+        ]);
+        assert_eq!(
+            value.to_debug_wire_string(),
+            "*2\\r\\n:1\\r\\n$2\\r\\na\\x00\\r\\n"
+        );
+        assert_eq!(
+            value.to_debug_wire_string_with(WireStyle::Multiline),
+            "*2\\r\\n\n:1\\r\\n\n$2\\r\\n\na\\x00\\r\\n"
+        );
+    }
+
+    #[test]
+    fn short_payload_ignores_hexdump_threshold() {
+        let have = Escaped::new(b"abc").hexdump_above(3).to_string();
+        assert_eq!(have, "\"abc\"");
+    }
+
+    #[test]
+    fn long_payload_uses_hexdump() {
+        let data: Vec<u8> = (0x3e..0x50).collect();
+        let have = Escaped::new(&data).hexdump_above(3).to_string();
+        let want =
+            "00000000  3e 3f 40 41 42 43 44 45  46 47 48 49 4a 4b 4c 4d  |>?@ABCDEFGHIJKLM|\n\
+                    00000010  4e 4f                                             |NO|";
+        assert_eq!(have, want);
+    }
+
+    #[test]
+    fn hexdump_masks_unprintable_bytes() {
+        let have = HexDump(b"\x00A\n").to_string();
+        assert_eq!(
+            have,
+            "00000000  00 41 0a                                          |.A.|"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{Error, RESPType, Result};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How the payload of an extension frame is delimited on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Framing {
+    /// The payload runs up to the next CRLF, like a simple string.
+    Line,
+    /// The payload is length-prefixed, like a bulk string.
+    Blob,
+}
+
+/// A frame introduced by a type byte registered in an
+/// [`ExtensionRegistry`]. It encodes back to the same bytes it was parsed
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Extension {
+    pub tag: u8,
+    pub framing: Framing,
+    pub payload: Vec<u8>,
+}
+
+/// Converts the raw payload of an extension frame into a value.
+pub type DecodeFn = Box<dyn Fn(Extension) -> Result<RESPType> + Send>;
+
+/// Type bytes used by RESP2 and RESP3, which cannot be registered.
+const RESERVED: &[u8] = b"+-:$*_,#!=(%~|>.;\r\n";
+
+/// Maps otherwise unused type bytes to extension frame formats. Install it
+/// on a parser with [`crate::Parser::set_extensions`].
+#[derive(Default)]
+pub struct ExtensionRegistry {
+    entries: HashMap<u8, (Framing, Option<DecodeFn>)>,
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> ExtensionRegistry {
+        ExtensionRegistry::default()
+    }
+
+    /// Registers `tag` so that its frames parse into
+    /// [`RESPType::Extension`] values.
+    pub fn register(&mut self, tag: u8, framing: Framing) -> Result<()> {
+        self.insert(tag, framing, None)
+    }
+
+    /// Registers `tag` with a callback that turns its frames into arbitrary
+    /// values.
+    pub fn register_with<F>(&mut self, tag: u8, framing: Framing, decode: F) -> Result<()>
+    where
+        F: Fn(Extension) -> Result<RESPType> + Send + 'static,
+    {
+        self.insert(tag, framing, Some(Box::new(decode)))
+    }
+
+    fn insert(&mut self, tag: u8, framing: Framing, decode: Option<DecodeFn>) -> Result<()> {
+        if RESERVED.contains(&tag) {
+            return Err(Error::ReservedTypeByte(tag));
+        }
+        self.entries.insert(tag, (framing, decode));
+        Ok(())
+    }
+
+    pub(crate) fn framing(&self, tag: u8) -> Option<Framing> {
+        self.entries.get(&tag).map(|e| e.0)
+    }
+
+    pub(crate) fn decode(&self, ext: Extension) -> Result<RESPType> {
+        match self.entries.get(&ext.tag) {
+            Some((_, Some(decode))) => decode(ext),
+            _ => Ok(RESPType::Extension(ext)),
+        }
+    }
+}
+
+impl fmt::Debug for ExtensionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tags: Vec<&u8> = self.entries.keys().collect();
+        tags.sort();
+        f.debug_struct("ExtensionRegistry")
+            .field("tags", &tags)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::io::Read;
+
+    fn parser(input: &str, registry: ExtensionRegistry) -> Parser<&[u8]> {
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        parser.set_extensions(registry);
+        parser
+    }
+
+    #[test]
+    fn rejects_reserved_bytes() {
+        let mut registry = ExtensionRegistry::new();
+        for &b in b"+-:$*%>".iter() {
+            match registry.register(b, Framing::Line) {
+                Err(Error::ReservedTypeByte(x)) => assert_eq!(x, b),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_line_and_blob_extensions() {
+        let mut registry = ExtensionRegistry::new();
+        registry.register(b'@', Framing::Line).unwrap();
+        registry.register(b'^', Framing::Blob).unwrap();
+        let mut parser = parser("*2\r\n@hello\r\n^3\r\na\r\n\r\n", registry);
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::Array(vec![
+                RESPType::Extension(Extension {
+                    tag: b'@',
+                    framing: Framing::Line,
+                    payload: b"hello".to_vec(),
+                }),
+                RESPType::Extension(Extension {
+                    tag: b'^',
+                    framing: Framing::Blob,
+                    payload: b"a\r\n".to_vec(),
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn applies_decode_callback() {
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .register_with(b'@', Framing::Line, |ext| {
+                Ok(RESPType::Integer(ext.payload.len() as i64))
+            })
+            .unwrap();
+        let mut parser = parser("@hello\r\n", registry);
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(5));
+    }
+
+    #[test]
+    fn unregistered_bytes_are_still_rejected() {
+        let mut parser = parser("@hello\r\n", ExtensionRegistry::new());
+        assert!(parser.parse_next().is_err());
+    }
+}
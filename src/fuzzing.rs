@@ -0,0 +1,170 @@
+//! Harness for the targets in `fuzz/`. Each function panics if it finds a
+//! bug, and must not panic on any other input.
+
+use crate::parse::Parser;
+use crate::testgen::Generator;
+use crate::{encode, parse_exact, parse_slice, split, validate, Decoder, ParseOptions, RESPType};
+use std::io::{self, Read};
+
+/// Feeds arbitrary bytes to the reading and buffered parsers in strict and
+/// lenient modes, and to the borrowing parser, frame splitter and validator.
+pub fn parse_arbitrary(data: &[u8]) {
+    for options in &[ParseOptions::strict(), ParseOptions::lenient()] {
+        let mut parser = Parser::with_options(data.bytes(), options.clone());
+        while parser.parse_next().is_ok() {}
+        assert!(parser.offset() <= data.len() as u64);
+
+        let mut parser = Parser::buffered_with_options(data, options.clone());
+        while parser.parse_next().is_ok() {}
+        assert!(parser.offset() <= data.len() as u64);
+    }
+    let mut rest = data;
+    while let Ok((_, len)) = parse_slice(rest) {
+        assert!(len > 0 && len <= rest.len());
+        rest = &rest[len..];
+    }
+    for slice in split::frames(data).flatten() {
+        let _ = parse_exact(slice);
+    }
+    let _ = validate::validate_report(data);
+}
+
+/// Checks that every value parsed from `data`, and every value generated
+/// from a seed taken from `data`, encodes to bytes that parse back to it.
+pub fn round_trip(data: &[u8]) {
+    let mut parser = Parser::new(data.bytes());
+    while let Ok(value) = parser.parse_next() {
+        assert_round_trips(&value);
+    }
+    let mut seed = [0u8; 8];
+    let n = data.len().min(8);
+    seed[..n].copy_from_slice(&data[..n]);
+    let mut gen = Generator::new(u64::from_le_bytes(seed)).resp3();
+    for _ in 0..4 {
+        assert_round_trips(&gen.value());
+    }
+}
+
+fn assert_round_trips(value: &RESPType) {
+    let buf = encode::to_vec(value).expect("generated value must encode");
+    let parsed = parse_exact(&buf).expect("encoded value must parse");
+    assert_eq!(&parsed, value);
+}
+
+/// Checks that the outcome of parsing does not depend on how the input is
+/// split into reads, or into calls to [`Decoder::feed`]. The first byte of
+/// `data` seeds the chunk sizes.
+pub fn chunked_feed(data: &[u8]) {
+    let (seed, input) = match data.split_first() {
+        Some((&seed, input)) => (seed, input),
+        None => return,
+    };
+    let whole = parse_all(Parser::new(input.bytes()));
+    let pieces = Pieces {
+        buf: input,
+        state: u32::from(seed),
+    };
+    let reader = io::BufReader::with_capacity(8, pieces);
+    assert_eq!(parse_all(Parser::new(reader.bytes())), whole);
+
+    let mut parser = Parser::new(input.bytes());
+    let mut consumed = 0;
+    while parser.parse_next_chunked(|_| {}).is_ok() {
+        consumed = parser.offset();
+    }
+    assert_eq!(consumed, whole.1);
+
+    let mut pieces = Pieces {
+        buf: input,
+        state: u32::from(seed),
+    };
+    assert_eq!(decode_all(&mut pieces), whole.0);
+}
+
+/// Feeds the chunks read from `pieces` to a [`Decoder`], returning the
+/// frames it produces before the first error.
+fn decode_all(pieces: &mut Pieces<'_>) -> Vec<RESPType> {
+    let mut decoder = Decoder::new();
+    let mut values = Vec::new();
+    let mut chunk = [0u8; 8];
+    loop {
+        let n = pieces.read(&mut chunk).expect("reading from a slice");
+        let mut data = &chunk[..n];
+        loop {
+            match decoder.feed(data) {
+                Ok(Some(value)) => values.push(value),
+                Ok(None) => break,
+                Err(_) => return values,
+            }
+            data = &[];
+        }
+        if n == 0 {
+            return values;
+        }
+    }
+}
+
+/// Parses frames until the first error, returning them with the offset
+/// reached after the last good frame.
+fn parse_all<R: io::Read>(mut parser: Parser<R>) -> (Vec<RESPType>, u64) {
+    let mut values = Vec::new();
+    let mut offset = 0;
+    while let Ok(value) = parser.parse_next() {
+        values.push(value);
+        offset = parser.offset();
+    }
+    (values, offset)
+}
+
+/// A reader that returns its buffer in pseudo-randomly sized reads.
+struct Pieces<'a> {
+    buf: &'a [u8],
+    state: u32,
+}
+
+impl<'a> io::Read for Pieces<'a> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.state = self.state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let n = ((self.state >> 16) as usize % 7 + 1)
+            .min(out.len())
+            .min(self.buf.len());
+        out[..n].copy_from_slice(&self.buf[..n]);
+        self.buf = &self.buf[n..];
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: &[&[u8]] = &[
+        b"",
+        b"\x03*2\r\n$3\r\nfoo\r\n:12\r\n+OK\r\n",
+        b"\x00$-1\r\n*-1\r\n-ERR x\r\n:+7\r\n",
+        b"\xff$5\r\nab",
+        b"\x10*3\r\n:1\r\n",
+        b"\x42\x00\xff\r\n\n",
+    ];
+
+    #[test]
+    fn harness_accepts_seed_inputs() {
+        for data in SEEDS {
+            parse_arbitrary(data);
+            round_trip(data);
+            chunked_feed(data);
+        }
+    }
+
+    #[test]
+    fn harness_accepts_generated_streams() {
+        let mut gen = Generator::new(99);
+        for i in 0..20u8 {
+            let mut data = vec![i];
+            data.extend(gen.stream(5));
+            parse_arbitrary(&data);
+            round_trip(&data);
+            chunked_feed(&data);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Protocol negotiation with `HELLO`.
+
+use crate::encode::encode_command;
+use crate::reply::hello::{parse_hello, ServerHello};
+use crate::{Error, Parser, ProtocolVersion, RESPType, Result};
+use std::io;
+
+/// Builds a `HELLO` command and applies the server's reply to a parser.
+#[derive(Debug, Clone)]
+pub struct Hello {
+    protocol: ProtocolVersion,
+    auth: Option<(String, String)>,
+    client_name: Option<String>,
+}
+
+impl Hello {
+    pub fn new(protocol: ProtocolVersion) -> Hello {
+        Hello {
+            protocol,
+            auth: None,
+            client_name: None,
+        }
+    }
+
+    /// Authenticates as part of the handshake, as `HELLO 3 AUTH user pass`.
+    pub fn auth(mut self, username: &str, password: &str) -> Hello {
+        self.auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Names the connection, as `CLIENT SETNAME` would.
+    pub fn client_name(mut self, name: &str) -> Hello {
+        self.client_name = Some(name.to_string());
+        self
+    }
+
+    /// The encoded command, ready to be written to the connection.
+    pub fn command(&self) -> Vec<u8> {
+        let mut args: Vec<&str> = vec!["HELLO", version_arg(self.protocol)];
+        if let Some((username, password)) = &self.auth {
+            args.extend(&["AUTH", username.as_str(), password.as_str()]);
+        }
+        if let Some(name) = &self.client_name {
+            args.extend(&["SETNAME", name.as_str()]);
+        }
+        encode_command(args)
+    }
+
+    /// Decodes the reply to [`Hello::command`] and switches `parser` to the
+    /// protocol the server agreed to. An error reply, such as `NOPROTO` from
+    /// a server without RESP3 support, leaves the parser unchanged.
+    pub fn negotiate<R: io::Read>(
+        &self,
+        parser: &mut Parser<R>,
+        reply: &RESPType,
+    ) -> Result<ServerHello> {
+        let hello = parse_hello(reply)?;
+        let protocol = match hello.proto {
+            2 => ProtocolVersion::Resp2,
+            3 => ProtocolVersion::Resp3,
+            n => return Err(Error::InvalidReply(format!("unknown protocol {}", n))),
+        };
+        parser.set_protocol(protocol);
+        Ok(hello)
+    }
+}
+
+fn version_arg(protocol: ProtocolVersion) -> &'static str {
+    match protocol {
+        ProtocolVersion::Resp2 => "2",
+        ProtocolVersion::Resp3 => "3",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn builds_command() {
+        assert_eq!(
+            Hello::new(ProtocolVersion::Resp3).command(),
+            b"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n".to_vec()
+        );
+        let command = Hello::new(ProtocolVersion::Resp2)
+            .auth("default", "secret")
+            .client_name("app")
+            .command();
+        assert_eq!(
+            command,
+            encode_command(&["HELLO", "2", "AUTH", "default", "secret", "SETNAME", "app"])
+        );
+    }
+
+    #[test]
+    fn switches_parser_protocol() {
+        let reply = b"*6\r\n$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n$5\r\n7.2.4\r\n$5\r\nproto\r\n:2\r\n";
+        let mut parser = Parser::new(reply.bytes());
+        let reply = parser.parse_next().unwrap();
+        let hello = Hello::new(ProtocolVersion::Resp2)
+            .negotiate(&mut parser, &reply)
+            .unwrap();
+        assert_eq!(hello.version, "7.2.4");
+        assert_eq!(parser.protocol(), ProtocolVersion::Resp2);
+    }
+
+    #[test]
+    fn error_reply_keeps_protocol() {
+        let mut parser = Parser::new(b"".bytes());
+        let reply = RESPType::Error("NOPROTO unsupported protocol version".to_string());
+        assert!(Hello::new(ProtocolVersion::Resp3)
+            .negotiate(&mut parser, &reply)
+            .is_err());
+        assert_eq!(parser.protocol(), ProtocolVersion::Resp3);
+    }
+}
@@ -0,0 +1,185 @@
+//! Inline commands, the telnet-friendly alternative to sending a RESP
+//! array: space-separated words on one line, e.g. `SET key "a value"`.
+//!
+//! Quoting follows the rules of Redis' own `sdssplitargs`. Inside double
+//! quotes, `\n`, `\r`, `\t`, `\b`, `\a` and `\xHH` escapes are decoded and
+//! any other escaped byte stands for itself. Inside single quotes only `\'`
+//! is an escape. A closing quote must be followed by a space or the end of
+//! the line.
+
+use crate::{Error, RESPType, Result};
+
+/// Parses the inline command at the start of `buf`, returning it as an
+/// array of bulk strings together with the number of bytes it took up,
+/// including the line ending. Returns `None` if `buf` does not hold a whole
+/// line yet.
+///
+/// A blank line gives an empty array, which servers usually ignore.
+pub fn parse_inline(buf: &[u8]) -> Result<Option<(RESPType, usize)>> {
+    let end = match buf.iter().position(|&b| b == b'\n') {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+    let line = buf[..end].strip_suffix(b"\r").unwrap_or(&buf[..end]);
+    let args = split_args(line)?
+        .into_iter()
+        .map(RESPType::BulkString)
+        .collect();
+    Ok(Some((RESPType::Array(args), end + 1)))
+}
+
+/// Whether a request starting with `b` is an inline command rather than a
+/// RESP array.
+pub fn is_inline(b: u8) -> bool {
+    b != b'*'
+}
+
+/// Splits a line into words, decoding quotes and escapes.
+pub fn split_args(line: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut pos = 0;
+    loop {
+        while pos < line.len() && is_space(line[pos]) {
+            pos += 1;
+        }
+        if pos == line.len() {
+            return Ok(args);
+        }
+        let mut arg = Vec::new();
+        while pos < line.len() && !is_space(line[pos]) {
+            pos = match line[pos] {
+                b'"' => double_quoted(line, pos + 1, &mut arg)?,
+                b'\'' => single_quoted(line, pos + 1, &mut arg)?,
+                b => {
+                    arg.push(b);
+                    pos + 1
+                }
+            };
+        }
+        args.push(arg);
+    }
+}
+
+/// Decodes a double-quoted word starting after its opening quote and
+/// returns the position after the closing quote.
+fn double_quoted(line: &[u8], mut pos: usize, arg: &mut Vec<u8>) -> Result<usize> {
+    loop {
+        match line.get(pos..) {
+            Some([b'\\', b'x', hi, lo, ..]) if hex(*hi).is_some() && hex(*lo).is_some() => {
+                arg.push(hex(*hi).unwrap() << 4 | hex(*lo).unwrap());
+                pos += 4;
+            }
+            Some([b'\\', c, ..]) => {
+                arg.push(match c {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'a' => 0x07,
+                    c => *c,
+                });
+                pos += 2;
+            }
+            Some([b'"', ..]) => return closed(line, pos + 1),
+            Some([b, ..]) => {
+                arg.push(*b);
+                pos += 1;
+            }
+            _ => return Err(Error::UnbalancedQuotes),
+        }
+    }
+}
+
+fn single_quoted(line: &[u8], mut pos: usize, arg: &mut Vec<u8>) -> Result<usize> {
+    loop {
+        match line.get(pos..) {
+            Some([b'\\', b'\'', ..]) => {
+                arg.push(b'\'');
+                pos += 2;
+            }
+            Some([b'\'', ..]) => return closed(line, pos + 1),
+            Some([b, ..]) => {
+                arg.push(*b);
+                pos += 1;
+            }
+            _ => return Err(Error::UnbalancedQuotes),
+        }
+    }
+}
+
+fn closed(line: &[u8], pos: usize) -> Result<usize> {
+    match line.get(pos) {
+        Some(&b) if !is_space(b) => Err(Error::UnbalancedQuotes),
+        _ => Ok(pos),
+    }
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c)
+}
+
+fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str) -> Vec<Vec<u8>> {
+        split_args(line.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(
+            split("  SET  key\tvalue "),
+            vec![b"SET".to_vec(), b"key".to_vec(), b"value".to_vec()]
+        );
+        assert!(split("").is_empty());
+    }
+
+    #[test]
+    fn decodes_quotes() {
+        assert_eq!(
+            split(r#"SET "a \"b\"\n\x41" 'it\'s \n' pre"fix""#),
+            vec![
+                b"SET".to_vec(),
+                b"a \"b\"\nA".to_vec(),
+                b"it's \\n".to_vec(),
+                b"prefix".to_vec(),
+            ]
+        );
+        assert_eq!(split(r#""""#), vec![Vec::new()]);
+    }
+
+    #[test]
+    fn rejects_unbalanced_quotes() {
+        for line in &[r#"GET "key"#, "GET 'key", r#"GET "a"b"#, r#"GET "a\""#] {
+            match split_args(line.as_bytes()) {
+                Err(Error::UnbalancedQuotes) => {}
+                other => panic!("{:?}: {:?}", line, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_lines() {
+        assert_eq!(parse_inline(b"PING").unwrap(), None);
+        let (value, len) = parse_inline(b"ECHO hi\r\nPING\n").unwrap().unwrap();
+        assert_eq!(
+            value,
+            RESPType::Array(vec![
+                RESPType::BulkString(b"ECHO".to_vec()),
+                RESPType::BulkString(b"hi".to_vec()),
+            ])
+        );
+        assert_eq!(len, 9);
+        assert_eq!(
+            parse_inline(b"\r\n").unwrap(),
+            Some((RESPType::Array(Vec::new()), 2))
+        );
+        assert!(is_inline(b'P'));
+        assert!(!is_inline(b'*'));
+    }
+}
@@ -0,0 +1,117 @@
+/// Builds a [`RESPType`](crate::RESPType) from a JSON-like literal.
+///
+/// `[...]` becomes an array, `{ key: value, ... }` a map and `null` a null.
+/// Anything else is an expression converted with
+/// [`ToResp`](crate::ToResp), so strings become bulk strings. A map key
+/// that is more than one token must be wrapped in parentheses.
+///
+/// ```
+/// use resp_rs::{resp, RESPType};
+///
+/// let key = "user:1";
+/// assert_eq!(
+///     resp!(["GET", key]),
+///     RESPType::Array(vec![
+///         RESPType::BulkString(b"GET".to_vec()),
+///         RESPType::BulkString(b"user:1".to_vec()),
+///     ])
+/// );
+/// let reply = resp!({ "name": "x", "age": 3, "tags": [], "parent": null });
+/// ```
+#[macro_export]
+macro_rules! resp {
+    (null) => {
+        $crate::RESPType::Null
+    };
+    ([ $($items:tt)* ]) => {
+        $crate::RESPType::Array($crate::resp!(@items [] $($items)*))
+    };
+    ({ $($entries:tt)* }) => {
+        $crate::RESPType::Map($crate::resp!(@entries [] $($entries)*))
+    };
+
+    // Arrays are munched one element at a time, since an element may be a
+    // nested literal that is not a valid expression.
+    (@items [$($done:expr,)*]) => {
+        vec![$($done,)*]
+    };
+    (@items [$($done:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::resp!(@items [$($done,)* $crate::RESPType::Null,] $($($rest)*)?)
+    };
+    (@items [$($done:expr,)*] [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::resp!(@items [$($done,)* $crate::resp!([$($inner)*]),] $($($rest)*)?)
+    };
+    (@items [$($done:expr,)*] {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::resp!(@items [$($done,)* $crate::resp!({$($inner)*}),] $($($rest)*)?)
+    };
+    (@items [$($done:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::resp!(@items [$($done,)* $crate::resp!($next),] $($($rest)*)?)
+    };
+
+    (@entries [$($done:expr,)*]) => {
+        vec![$($done,)*]
+    };
+    (@entries [$($done:expr,)*] $key:tt : null $(, $($rest:tt)*)?) => {
+        $crate::resp!(@entries [$($done,)* ($crate::resp!($key), $crate::RESPType::Null),] $($($rest)*)?)
+    };
+    (@entries [$($done:expr,)*] $key:tt : [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::resp!(@entries [$($done,)* ($crate::resp!($key), $crate::resp!([$($inner)*])),] $($($rest)*)?)
+    };
+    (@entries [$($done:expr,)*] $key:tt : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::resp!(@entries [$($done,)* ($crate::resp!($key), $crate::resp!({$($inner)*})),] $($($rest)*)?)
+    };
+    (@entries [$($done:expr,)*] $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::resp!(@entries [$($done,)* ($crate::resp!($key), $crate::resp!($value)),] $($($rest)*)?)
+    };
+
+    ($other:expr) => {
+        $crate::ToResp::to_resp(&$other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::bulk;
+    use crate::RESPType;
+
+    #[test]
+    fn builds_arrays() {
+        let key = String::from("k");
+        assert_eq!(
+            resp!(["GET", key, -1, [1, null], 2 + 3,]),
+            RESPType::Array(vec![
+                bulk("GET"),
+                bulk("k"),
+                RESPType::Integer(-1),
+                RESPType::Array(vec![RESPType::Integer(1), RESPType::Null]),
+                RESPType::Integer(5),
+            ])
+        );
+        assert_eq!(resp!([]), RESPType::Array(vec![]));
+    }
+
+    #[test]
+    fn builds_maps() {
+        let field = "age";
+        assert_eq!(
+            resp!({ "name": "x", field: 3, "tags": ["a"], "inner": { "ok": true }, "none": null }),
+            RESPType::Map(vec![
+                (bulk("name"), bulk("x")),
+                (bulk("age"), RESPType::Integer(3)),
+                (bulk("tags"), RESPType::Array(vec![bulk("a")])),
+                (
+                    bulk("inner"),
+                    RESPType::Map(vec![(bulk("ok"), RESPType::Boolean(true))])
+                ),
+                (bulk("none"), RESPType::Null),
+            ])
+        );
+    }
+
+    #[test]
+    fn passes_values_through() {
+        let value = RESPType::Integer(7);
+        assert_eq!(resp!(value.clone()), value);
+        assert_eq!(resp!([value]), RESPType::Array(vec![RESPType::Integer(7)]));
+    }
+}
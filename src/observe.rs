@@ -0,0 +1,80 @@
+use crate::{Error, RESPKind};
+
+/// Describes a frame that was successfully parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub kind: RESPKind,
+    /// Offset of the first byte of the frame in the stream.
+    pub offset: u64,
+    /// Encoded length of the frame in bytes.
+    pub len: u64,
+}
+
+/// Hooks invoked by [`crate::Parser`] after each top-level frame, for
+/// accounting, sampling or tracing. Both methods default to doing nothing.
+pub trait ParseObserver: Send {
+    fn on_frame(&mut self, _frame: &FrameInfo) {}
+
+    /// Called with the error and the stream offset at which it was detected.
+    /// This includes the `EndOfStream` error returned at the end of input.
+    fn on_error(&mut self, _error: &Error, _offset: u64) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, RESPType};
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder {
+        frames: Arc<Mutex<Vec<FrameInfo>>>,
+        errors: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl ParseObserver for Recorder {
+        fn on_frame(&mut self, frame: &FrameInfo) {
+            self.frames.lock().unwrap().push(*frame);
+        }
+
+        fn on_error(&mut self, _error: &Error, offset: u64) {
+            self.errors.lock().unwrap().push(offset);
+        }
+    }
+
+    #[test]
+    fn reports_top_level_frames_and_errors() {
+        let recorder = Recorder::default();
+        let frames = recorder.frames.clone();
+        let errors = recorder.errors.clone();
+
+        let input = "*2\r\n:1\r\n:2\r\n+OK\r\n:x\r\n";
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        parser.set_observer(Box::new(recorder));
+
+        assert!(parser.parse_next().is_ok());
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::SimpleString("OK".to_string())
+        );
+        assert!(parser.parse_next().is_err());
+
+        assert_eq!(
+            *frames.lock().unwrap(),
+            vec![
+                FrameInfo {
+                    kind: RESPKind::Array,
+                    offset: 0,
+                    len: 12,
+                },
+                FrameInfo {
+                    kind: RESPKind::SimpleString,
+                    offset: 12,
+                    len: 5,
+                },
+            ]
+        );
+        assert_eq!(*errors.lock().unwrap(), vec![21]);
+    }
+}
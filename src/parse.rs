@@ -0,0 +1,2536 @@
+use crate::extension::{self, Extension, ExtensionRegistry};
+use crate::observe::{FrameInfo, ParseObserver};
+use crate::scan;
+use crate::transcode::{self, Transcoder};
+use crate::{Error, RESPKind, RESPType, Result};
+use std::io;
+
+/// Which version of the protocol a parser accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    /// Only the RESP2 types: simple strings, errors, integers, bulk strings
+    /// and arrays.
+    Resp2,
+    /// RESP2 plus every RESP3 type, including streamed frames.
+    #[default]
+    Resp3,
+}
+
+/// Type bytes introduced by RESP3.
+const RESP3_MARKERS: &[u8] = b"%~|>,#_(=!";
+
+/// The default [`ParseOptions::max_depth`]. Dropping, cloning and printing
+/// a [`RESPType`] recurse once per level, so values much deeper than this
+/// could overflow the call stack.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Controls how forgiving the parser is about deviations from the RESP spec.
+///
+/// The default is strict: lines must end with CRLF, and integer lines must
+/// match `-?[0-9]+` with no leading zeros (other than a lone `0`), no `+`
+/// sign and no whitespace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Accept a leading `+` sign on integers, e.g. `:+5`.
+    pub integer_plus_sign: bool,
+    /// Accept redundant leading zeros on integers, e.g. `:007`.
+    pub integer_leading_zeros: bool,
+    /// Accept spaces and tabs around integers, e.g. `: 5 `.
+    pub integer_whitespace: bool,
+    /// Accept and ignore bytes between a bulk payload and its CRLF, e.g.
+    /// `$1\r\nxy\r\n`.
+    pub bulk_trailing_bytes: bool,
+    /// Accept lines ended by a bare `\n`, dropping carriage returns
+    /// anywhere in a line instead of rejecting them.
+    pub loose_line_endings: bool,
+    /// Replace invalid UTF-8 in simple strings and errors with U+FFFD
+    /// instead of failing with [`Error::BadString`].
+    pub lossy_utf8: bool,
+    /// Return integer replies that do not fit in an `i64` as
+    /// [`RESPType::BigNumber`] instead of failing with
+    /// [`Error::BadInteger`].
+    pub integer_overflow_big_number: bool,
+    /// Abort a frame once its encoding exceeds this many bytes.
+    pub max_frame_bytes: Option<u64>,
+    /// Abort a frame once aggregates are nested more than this many levels
+    /// deep. A top-level array is one level. Defaults to
+    /// [`DEFAULT_MAX_DEPTH`]; without a limit, hostile input can build a
+    /// value too deep to drop.
+    pub max_depth: Option<usize>,
+    /// Reject RESP3 frames when set to [`ProtocolVersion::Resp2`].
+    pub protocol: ProtocolVersion,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            integer_plus_sign: false,
+            integer_leading_zeros: false,
+            integer_whitespace: false,
+            bulk_trailing_bytes: false,
+            loose_line_endings: false,
+            lossy_utf8: false,
+            integer_overflow_big_number: false,
+            max_frame_bytes: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            protocol: ProtocolVersion::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn strict() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    pub fn lenient() -> ParseOptions {
+        ParseOptions {
+            integer_plus_sign: true,
+            integer_leading_zeros: true,
+            integer_whitespace: true,
+            bulk_trailing_bytes: true,
+            loose_line_endings: true,
+            ..ParseOptions::default()
+        }
+    }
+
+    /// Parses the integer line `s`, which starts at `offset` in the input.
+    pub(crate) fn parse_int(&self, s: &str, offset: u64) -> Result<i64> {
+        let s = if self.integer_whitespace {
+            s.trim_matches(|c| c == ' ' || c == '\t')
+        } else {
+            s
+        };
+        let digits = match s.as_bytes().first() {
+            Some(b'-') => &s[1..],
+            Some(b'+') if self.integer_plus_sign => &s[1..],
+            _ => s,
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::MalformedInteger {
+                value: s.to_string(),
+                offset,
+            });
+        }
+        if !self.integer_leading_zeros && digits.len() > 1 && digits.starts_with('0') {
+            return Err(Error::MalformedInteger {
+                value: s.to_string(),
+                offset,
+            });
+        }
+        s.parse::<i64>()
+            .map_err(|source| Error::BadInteger { offset, source })
+    }
+}
+
+/// Receives bulk string payloads during [`Parser::parse_next_chunked`].
+type ChunkSink<'a, 'f> = Option<&'a mut (dyn FnMut(&[u8]) + 'f)>;
+
+/// Largest chunk handed to the callback of [`Parser::parse_next_chunked`].
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Most memory reserved up front for a bulk payload, so that a bogus length
+/// cannot make the parser allocate more than the input actually holds.
+const PREALLOC_LIMIT: usize = 1024 * 1024;
+
+/// Default size of the read buffer of a [`Parser::buffered`] parser.
+pub const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+enum Source<R> {
+    Bytes(io::Bytes<R>),
+    Reader(R),
+}
+
+/// The parser's input: bytes are read from the source into `buf`, in
+/// blocks for a reader and one at a time for an `io::Bytes`, and handed
+/// out from `buf[pos..end]`.
+struct Input<R> {
+    source: Source<R>,
+    buf: Box<[u8]>,
+    pos: usize,
+    end: usize,
+}
+
+impl<R: io::Read> Input<R> {
+    /// Returns the unconsumed bytes, reading more if there are none left.
+    /// An empty slice means the end of input.
+    fn fill(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.end {
+            let n = match &mut self.source {
+                Source::Bytes(bytes) => match bytes.next().transpose()? {
+                    Some(b) => {
+                        self.buf[0] = b;
+                        1
+                    }
+                    None => 0,
+                },
+                Source::Reader(reader) => loop {
+                    match reader.read(&mut self.buf) {
+                        Ok(n) => break n,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => return Err(err),
+                    }
+                },
+            };
+            self.pos = 0;
+            self.end = n;
+        }
+        Ok(&self.buf[self.pos..self.end])
+    }
+
+    /// Reads and discards the rest of the input, returning its length.
+    fn discard_rest(&mut self) -> io::Result<usize> {
+        let mut discarded = 0;
+        loop {
+            let n = self.fill()?.len();
+            if n == 0 {
+                return Ok(discarded);
+            }
+            discarded += n;
+            self.pos += n;
+        }
+    }
+}
+
+pub struct Parser<R: io::Read> {
+    input: Input<R>,
+    options: ParseOptions,
+    offset: u64,
+    frame_start: u64,
+    observer: Option<Box<dyn ParseObserver>>,
+    transcoders: Vec<Box<dyn Transcoder>>,
+    extensions: Option<ExtensionRegistry>,
+    raw: Option<Vec<u8>>,
+}
+
+impl<R: io::Read> Parser<R> {
+    /// Pulls bytes one at a time from `bytes`. Prefer [`Parser::buffered`]
+    /// for sockets and files.
+    pub fn new(bytes: io::Bytes<R>) -> Parser<R> {
+        Parser::with_options(bytes, ParseOptions::default())
+    }
+
+    pub fn with_options(bytes: io::Bytes<R>, options: ParseOptions) -> Parser<R> {
+        Parser::from_input(
+            Input {
+                source: Source::Bytes(bytes),
+                buf: Box::new([0]),
+                pos: 0,
+                end: 0,
+            },
+            options,
+        )
+    }
+
+    /// Reads from `reader` in blocks through an internal buffer, scanning
+    /// lines and payloads a block at a time. This is much faster than
+    /// [`Parser::new`], but the reader may be read past the end of the
+    /// last frame parsed.
+    pub fn buffered(reader: R) -> Parser<R> {
+        Parser::buffered_with_options(reader, ParseOptions::default())
+    }
+
+    pub fn buffered_with_options(reader: R, options: ParseOptions) -> Parser<R> {
+        Parser::buffered_with_capacity(reader, DEFAULT_BUF_SIZE, options)
+    }
+
+    /// Like [`Parser::buffered_with_options`], reading `reader` in blocks
+    /// of up to `capacity` bytes, at least one. The buffer is allocated
+    /// once and never grows, since payloads are copied out of it as they
+    /// are read.
+    pub fn buffered_with_capacity(reader: R, capacity: usize, options: ParseOptions) -> Parser<R> {
+        Parser::from_input(
+            Input {
+                source: Source::Reader(reader),
+                buf: vec![0; capacity.max(1)].into_boxed_slice(),
+                pos: 0,
+                end: 0,
+            },
+            options,
+        )
+    }
+
+    fn from_input(input: Input<R>, options: ParseOptions) -> Parser<R> {
+        Parser {
+            input,
+            options,
+            offset: 0,
+            frame_start: 0,
+            observer: None,
+            transcoders: Vec::new(),
+            extensions: None,
+            raw: None,
+        }
+    }
+
+    /// Takes back the reader of a [`Parser::buffered`] parser, along with
+    /// the bytes already read from it but not yet parsed, e.g. to hand the
+    /// connection to other code. Returns `None` for a parser created from an
+    /// `io::Bytes`, which cannot be unwrapped.
+    pub fn into_inner(self) -> Option<(R, Vec<u8>)> {
+        match self.input.source {
+            Source::Reader(reader) => Some((
+                reader,
+                self.input.buf[self.input.pos..self.input.end].to_vec(),
+            )),
+            Source::Bytes(_) => None,
+        }
+    }
+
+    /// Switches the protocol accepted from the next frame on, e.g. after a
+    /// successful `HELLO 3`.
+    pub fn set_protocol(&mut self, protocol: ProtocolVersion) {
+        self.options.protocol = protocol;
+    }
+
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.options.protocol
+    }
+
+    /// Installs an observer that is notified of every top-level frame and
+    /// every error returned by [`Parser::parse_next`].
+    pub fn set_observer(&mut self, observer: Box<dyn ParseObserver>) {
+        self.observer = Some(observer);
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn ParseObserver>> {
+        self.observer.take()
+    }
+
+    /// Registers a transcoder for bulk string payloads. Transcoders are tried
+    /// in registration order and the first match wins.
+    pub fn add_transcoder(&mut self, transcoder: Box<dyn Transcoder>) {
+        self.transcoders.push(transcoder);
+    }
+
+    /// Enables parsing of the extension frames described by `registry`.
+    pub fn set_extensions(&mut self, registry: ExtensionRegistry) {
+        self.extensions = Some(registry);
+    }
+
+    /// Keeps a copy of the bytes of each frame parsed, available from
+    /// [`Parser::last_frame_bytes`] until the next frame is parsed.
+    pub fn set_capture_frames(&mut self, enabled: bool) {
+        self.raw = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// The exact bytes of the last frame parsed, if capture is enabled. If
+    /// parsing failed, these are the bytes consumed before the error.
+    pub fn last_frame_bytes(&self) -> Option<&[u8]> {
+        self.raw.as_deref()
+    }
+
+    /// Number of bytes consumed from the reader so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn parse_next(&mut self) -> Result<RESPType> {
+        let start = self.offset;
+        self.frame_start = start;
+        if let Some(raw) = self.raw.as_mut() {
+            raw.clear();
+        }
+        let result = self.parse_value();
+        self.notify(start, result, RESPType::kind)
+    }
+
+    /// Parses one frame and reads the rest of the input, failing with
+    /// [`Error::TrailingBytes`] unless it is empty. For slices, see
+    /// [`parse_exact`].
+    pub fn parse_complete(&mut self) -> Result<RESPType> {
+        let value = self.parse_next()?;
+        let offset = self.offset;
+        match self.input.discard_rest()? {
+            0 => Ok(value),
+            len => Err(Error::TrailingBytes { len, offset }),
+        }
+    }
+
+    /// Parses the next frame and returns it along with its exact bytes, e.g.
+    /// to relay it verbatim. Works whether or not frame capture is enabled.
+    pub fn parse_next_raw(&mut self) -> Result<(RESPType, Vec<u8>)> {
+        let capturing = self.raw.is_some();
+        if !capturing {
+            self.raw = Some(Vec::new());
+        }
+        let value = self.parse_next();
+        let raw = if capturing {
+            self.raw.clone()
+        } else {
+            self.raw.take()
+        };
+        Ok((value?, raw.unwrap_or_default()))
+    }
+
+    /// Parses the next frame, unless it is a bulk string longer than
+    /// `threshold` bytes, in which case a reader over its payload is
+    /// returned instead, e.g. to copy a large `DUMP` reply to disk.
+    ///
+    /// The observer is not told about frames returned as readers, and
+    /// transcoders are not applied to them.
+    pub fn stream_next(&mut self, threshold: u64) -> Result<Streaming<'_, R>> {
+        let start = self.offset;
+        self.frame_start = start;
+        if let Some(raw) = self.raw.as_mut() {
+            raw.clear();
+        }
+        let result = match self.next_byte() {
+            Ok(Some(b'$')) => match self.parse_length() {
+                Ok(Some(n)) if n >= 0 && n as u64 > threshold => {
+                    return Ok(Streaming::Bulk(BulkReader {
+                        parser: self,
+                        remaining: n as u64,
+                        done: false,
+                    }));
+                }
+                Ok(len) => self.parse_bulk_body(len, start + 1),
+                Err(err) => Err(err),
+            },
+            Ok(Some(b)) => self.parse_value_from(Some(b), None),
+            Ok(None) => Err(Error::EndOfStream),
+            Err(err) => Err(err),
+        };
+        self.notify(start, result, RESPType::kind)
+            .map(Streaming::Value)
+    }
+
+    /// Parses the next frame, handing bulk string payloads to `on_chunk` in
+    /// pieces of at most 8 KiB instead of accumulating them. Each payload is
+    /// followed by an empty chunk, and the returned value holds empty bulk
+    /// strings in their place. Transcoders are not applied.
+    pub fn parse_next_chunked<F: FnMut(&[u8])>(&mut self, mut on_chunk: F) -> Result<RESPType> {
+        let start = self.offset;
+        self.frame_start = start;
+        if let Some(raw) = self.raw.as_mut() {
+            raw.clear();
+        }
+        let result = self.parse_value_into(Some(&mut on_chunk));
+        self.notify(start, result, RESPType::kind)
+    }
+
+    /// Consumes the next frame without building it and returns its type.
+    /// Bulk payloads are read through and discarded rather than buffered,
+    /// so ignoring a large reply costs no more memory than a small one.
+    pub fn skip_next(&mut self) -> Result<RESPKind> {
+        let start = self.offset;
+        self.frame_start = start;
+        if let Some(raw) = self.raw.as_mut() {
+            raw.clear();
+        }
+        let result = self.skip_value();
+        self.notify(start, result, |kind| *kind)
+    }
+
+    fn notify<T>(
+        &mut self,
+        start: u64,
+        result: Result<T>,
+        kind: impl Fn(&T) -> RESPKind,
+    ) -> Result<T> {
+        // Running out of input is only a clean end of stream between frames.
+        let result = match result {
+            Err(Error::EndOfStream) if self.offset > start => {
+                Err(Error::Incomplete { needed: None })
+            }
+            other => other,
+        };
+        if let Some(observer) = self.observer.as_mut() {
+            match &result {
+                Ok(value) => observer.on_frame(&FrameInfo {
+                    kind: kind(value),
+                    offset: start,
+                    len: self.offset - start,
+                }),
+                Err(err) => observer.on_error(err, self.offset),
+            }
+        }
+        result
+    }
+
+    /// Parses the next frame using `options` instead of the parser's own
+    /// options, which are restored afterwards.
+    pub fn parse_next_with(&mut self, options: &ParseOptions) -> Result<RESPType> {
+        let defaults = std::mem::replace(&mut self.options, options.clone());
+        let result = self.parse_next();
+        self.options = defaults;
+        result
+    }
+
+    /// Parses the next frame, failing with [`Error::FrameTooLarge`] as soon
+    /// as it exceeds `max_bytes`, regardless of the parser's own limit.
+    pub fn parse_next_with_limit(&mut self, max_bytes: u64) -> Result<RESPType> {
+        let options = ParseOptions {
+            max_frame_bytes: Some(max_bytes),
+            ..self.options.clone()
+        };
+        self.parse_next_with(&options)
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Iterates over the remaining frames, ending at a clean end of input.
+    pub fn frames(&mut self) -> Frames<'_, R> {
+        Frames {
+            parser: self,
+            done: false,
+        }
+    }
+
+    /// Iterates over the remaining input as [`Event`]s, so that aggregates
+    /// can be processed element by element without materializing them.
+    pub fn events(&mut self) -> Events<'_, R> {
+        Events {
+            parser: self,
+            stack: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the buffered input, reading more if needed, cut short where
+    /// the frame size limit would be exceeded. Empty at the end of input.
+    fn available(&mut self) -> Result<&[u8]> {
+        let allowed = match self.options.max_frame_bytes {
+            Some(limit) => {
+                let used = self.offset - self.frame_start;
+                if used >= limit {
+                    return Err(Error::FrameTooLarge {
+                        limit,
+                        offset: self.frame_start,
+                    });
+                }
+                limit - used
+            }
+            None => u64::MAX,
+        };
+        let chunk = self.input.fill()?;
+        let n = (chunk.len() as u64).min(allowed) as usize;
+        Ok(&chunk[..n])
+    }
+
+    /// Marks the first `n` bytes returned by [`Parser::available`] as read.
+    fn consume(&mut self, n: usize) {
+        let input = &mut self.input;
+        if let Some(raw) = self.raw.as_mut() {
+            raw.extend_from_slice(&input.buf[input.pos..input.pos + n]);
+        }
+        input.pos += n;
+        self.offset += n as u64;
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        let byte = self.available()?.first().copied();
+        if byte.is_some() {
+            self.consume(1);
+        }
+        Ok(byte)
+    }
+
+    /// Reads exactly `len` bytes, handing them to `f` as they become
+    /// available.
+    fn read_exact_with<F: FnMut(&[u8])>(&mut self, len: u64, mut f: F) -> Result<()> {
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = self.available()?;
+            if chunk.is_empty() {
+                return Err(Error::EndOfStream);
+            }
+            let n = (chunk.len() as u64).min(remaining) as usize;
+            f(&chunk[..n]);
+            self.consume(n);
+            remaining -= n as u64;
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<RESPType> {
+        self.parse_value_into(None)
+    }
+
+    fn parse_value_into(&mut self, sink: ChunkSink<'_, '_>) -> Result<RESPType> {
+        self.parse_value_from(None, sink)
+    }
+
+    /// Parses one value, whose type byte is `first` if it has already been
+    /// read. With a chunk sink, bulk string payloads are handed to it
+    /// instead of being accumulated.
+    ///
+    /// Aggregates are tracked on an explicit stack rather than by
+    /// recursion, so parsing itself cannot overflow the call stack however
+    /// deep the input nests.
+    fn parse_value_from(
+        &mut self,
+        mut first: Option<u8>,
+        mut sink: ChunkSink<'_, '_>,
+    ) -> Result<RESPType> {
+        let mut stack: Vec<Pending> = Vec::new();
+        loop {
+            let at_boundary = stack.last().is_some_and(Pending::at_streamed_boundary);
+            let b = match first.take() {
+                Some(b) => b,
+                None => match self.next_byte()? {
+                    Some(b) => b,
+                    None => return Err(Error::EndOfStream),
+                },
+            };
+            let header = self.offset - 1;
+            let mut value = if at_boundary && b == b'.' {
+                self.read_end_marker()?;
+                let done = stack.pop().expect("streamed aggregate");
+                match close(&mut stack, done) {
+                    Some(value) => value,
+                    None => continue,
+                }
+            } else {
+                match self.parse_frame(b, sink.as_deref_mut())? {
+                    Frame::Value(value) => value,
+                    Frame::Aggregate(pending) => {
+                        if let Some(limit) = self.options.max_depth {
+                            if stack.len() >= limit {
+                                return Err(Error::NestingTooDeep {
+                                    limit,
+                                    offset: header,
+                                });
+                            }
+                        }
+                        if !pending.is_complete() {
+                            stack.push(pending);
+                            continue;
+                        }
+                        match close(&mut stack, pending) {
+                            Some(value) => value,
+                            None => continue,
+                        }
+                    }
+                }
+            };
+            // Hand the value to its parent, closing every aggregate it fills.
+            loop {
+                let top = match stack.last_mut() {
+                    Some(top) => top,
+                    None => return Ok(value),
+                };
+                top.push(value);
+                if !top.is_complete() {
+                    break;
+                }
+                let done = stack.pop().expect("complete aggregate");
+                match close(&mut stack, done) {
+                    Some(parent_item) => value = parent_item,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Result<RESPKind> {
+        // Frames left to skip at the current level and, for each enclosing
+        // streamed aggregate, the count to resume with after its end marker
+        // and the number of frames in each of its elements.
+        let mut remaining: u64 = 1;
+        let mut streamed: Vec<(u64, u64)> = Vec::new();
+        let mut kind = None;
+        loop {
+            if remaining == 0 && streamed.is_empty() {
+                return Ok(kind.expect("skipped a frame"));
+            }
+            let b = match self.next_byte()? {
+                Some(b) => b,
+                None => return Err(Error::EndOfStream),
+            };
+            if remaining == 0 {
+                let (resume, width) = *streamed.last().expect("streamed aggregate");
+                if b == b'.' {
+                    self.read_end_marker()?;
+                    streamed.pop();
+                    remaining = resume;
+                    continue;
+                }
+                remaining = width;
+            }
+            remaining -= 1;
+            if self.options.protocol == ProtocolVersion::Resp2 && RESP3_MARKERS.contains(&b) {
+                return Err(self.unexpected(b));
+            }
+            let offset = self.offset;
+            let frame_kind = match b {
+                b'*' | b'~' | b'>' | b'%' | b'|' => {
+                    let width = if b == b'%' || b == b'|' { 2 } else { 1 };
+                    // Attributes are followed by the value they apply to.
+                    let value = if b == b'|' { 1 } else { 0 };
+                    match self.parse_length()? {
+                        Some(-1) if b == b'*' => RESPKind::Null,
+                        Some(n) if n < 0 => return Err(invalid_length(n, offset)),
+                        len => {
+                            match len {
+                                Some(n) => {
+                                    remaining = (n as u64)
+                                        .checked_mul(width)
+                                        .and_then(|count| count.checked_add(value))
+                                        .and_then(|count| count.checked_add(remaining))
+                                        .ok_or_else(|| invalid_length(n, offset))?;
+                                }
+                                None => {
+                                    streamed.push((remaining + value, width));
+                                    remaining = 0;
+                                }
+                            }
+                            match b {
+                                b'*' => RESPKind::Array,
+                                b'~' => RESPKind::Set,
+                                b'>' => RESPKind::Push,
+                                b'%' => RESPKind::Map,
+                                _ => RESPKind::Attributed,
+                            }
+                        }
+                    }
+                }
+                b'$' => match self.parse_length()? {
+                    None => {
+                        while let Some(n) = self.next_string_chunk()? {
+                            self.skip_blob(n)?;
+                        }
+                        RESPKind::BulkString
+                    }
+                    Some(-1) => RESPKind::Null,
+                    Some(n) if n >= 0 => {
+                        self.skip_blob(n)?;
+                        RESPKind::BulkString
+                    }
+                    Some(n) => return Err(invalid_length(n, offset)),
+                },
+                b'=' | b'!' => match self.parse_integer()? {
+                    n if n >= 0 => {
+                        self.skip_blob(n)?;
+                        if b == b'=' {
+                            RESPKind::Verbatim
+                        } else {
+                            RESPKind::BulkError
+                        }
+                    }
+                    n => return Err(invalid_length(n, offset)),
+                },
+                // Other frames are single lines, cheap enough to parse.
+                b => self.parse_scalar(b)?.kind(),
+            };
+            kind.get_or_insert(frame_kind);
+        }
+    }
+
+    fn skip_blob(&mut self, len: i64) -> Result<()> {
+        self.read_exact_with(len as u64, |_| {})?;
+        self.read_trailer()
+    }
+
+    /// Reads the rest of the `.` line that ends a streamed aggregate.
+    fn read_end_marker(&mut self) -> Result<()> {
+        let start = self.offset;
+        match self.read_to_crlf()?.first() {
+            None => Ok(()),
+            Some(&b) => Err(Error::UnexpectedToken {
+                byte: b,
+                offset: start,
+            }),
+        }
+    }
+
+    /// Parses a scalar, or the header of an aggregate whose elements follow.
+    fn parse_frame(&mut self, b: u8, sink: ChunkSink<'_, '_>) -> Result<Frame> {
+        if self.options.protocol == ProtocolVersion::Resp2 && RESP3_MARKERS.contains(&b) {
+            return Err(self.unexpected(b));
+        }
+        let offset = self.offset;
+        let value = match b {
+            b'*' => match self.parse_length()? {
+                Some(-1) => RESPType::Null,
+                len => return pending_items(RESPKind::Array, len, offset),
+            },
+            b'~' => return pending_items(RESPKind::Set, self.parse_length()?, offset),
+            b'>' => return pending_items(RESPKind::Push, self.parse_length()?, offset),
+            b'%' | b'|' => {
+                let remaining = match self.parse_length()? {
+                    Some(n) if n < 0 => return Err(invalid_length(n, offset)),
+                    len => len,
+                };
+                return Ok(Frame::Aggregate(Pending::Entries {
+                    attributes: b == b'|',
+                    entries: Vec::new(),
+                    key: None,
+                    remaining,
+                }));
+            }
+            b'$' => match sink {
+                Some(on_chunk) => self.parse_bulk_str_chunked(on_chunk)?,
+                None => self.parse_bulk_str()?,
+            },
+            b => self.parse_scalar(b)?,
+        };
+        Ok(Frame::Value(value))
+    }
+
+    fn parse_scalar(&mut self, b: u8) -> Result<RESPType> {
+        match b as char {
+            '-' => self.parse_error(),
+            ':' => self.parse_integer_reply(),
+            '+' => self.parse_simple_str(),
+            ',' => self.parse_double(),
+            '#' => self.parse_boolean(),
+            '_' => self.parse_null(),
+            '(' => self.parse_big_number(),
+            '=' => self.parse_verbatim(),
+            '!' => self.parse_bulk_error(),
+            _ if self.extension_framing(b).is_some() => self.parse_extension(b),
+            _ => Err(self.unexpected(b)),
+        }
+    }
+
+    /// Rejects `byte`, the last byte consumed.
+    fn unexpected(&self, byte: u8) -> Error {
+        Error::UnexpectedToken {
+            byte,
+            offset: self.offset - 1,
+        }
+    }
+
+    fn parse_bulk_str_chunked(&mut self, on_chunk: &mut dyn FnMut(&[u8])) -> Result<RESPType> {
+        let offset = self.offset;
+        match self.parse_length()? {
+            None => {
+                while let Some(n) = self.next_string_chunk()? {
+                    self.stream_blob(n, on_chunk)?;
+                }
+            }
+            Some(-1) => return Ok(RESPType::Null),
+            Some(n) if n >= 0 => self.stream_blob(n, on_chunk)?,
+            Some(n) => return Err(invalid_length(n, offset)),
+        }
+        on_chunk(&[]);
+        Ok(RESPType::BulkString(Vec::new()))
+    }
+
+    fn stream_blob(&mut self, len: i64, on_chunk: &mut dyn FnMut(&[u8])) -> Result<()> {
+        let mut chunk: Vec<u8> = Vec::with_capacity((len as usize).min(CHUNK_SIZE));
+        self.read_exact_with(len as u64, |mut data| {
+            while !data.is_empty() {
+                let n = (CHUNK_SIZE - chunk.len()).min(data.len());
+                chunk.extend_from_slice(&data[..n]);
+                data = &data[n..];
+                if chunk.len() == CHUNK_SIZE {
+                    on_chunk(&chunk);
+                    chunk.clear();
+                }
+            }
+        })?;
+        if !chunk.is_empty() {
+            on_chunk(&chunk);
+        }
+        self.read_trailer()
+    }
+
+    fn parse_bulk_str(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        let len = self.parse_length()?;
+        self.parse_bulk_body(len, offset)
+    }
+
+    /// Reads the payload of a bulk string whose length line started at
+    /// `offset`.
+    fn parse_bulk_body(&mut self, len: Option<i64>, offset: u64) -> Result<RESPType> {
+        let buf = match len {
+            None => {
+                let mut buf = Vec::new();
+                while let Some(n) = self.next_string_chunk()? {
+                    buf.extend(self.read_blob(n)?);
+                }
+                buf
+            }
+            Some(-1) => return Ok(RESPType::Null),
+            Some(n) if n >= 0 => self.read_blob(n)?,
+            Some(n) => return Err(invalid_length(n, offset)),
+        };
+        let buf = transcode::decode_payload(&self.transcoders, buf)?;
+        Ok(RESPType::BulkString(buf))
+    }
+
+    /// Reads a length line, returning `None` for the `?` that introduces a
+    /// streamed frame.
+    fn parse_length(&mut self) -> Result<Option<i64>> {
+        let start = self.offset;
+        let line = self.read_str_line()?;
+        match line.as_str() {
+            "?" if self.options.protocol == ProtocolVersion::Resp3 => Ok(None),
+            _ => self.options.parse_int(&line, start).map(Some),
+        }
+    }
+
+    /// Reads the `;len` header of the next chunk of a streamed string,
+    /// returning `None` at the terminating `;0`.
+    fn next_string_chunk(&mut self) -> Result<Option<i64>> {
+        let offset = self.offset + 1;
+        match self.next_byte()? {
+            Some(b';') => match self.parse_integer()? {
+                0 => Ok(None),
+                n if n > 0 => Ok(Some(n)),
+                n => Err(invalid_length(n, offset)),
+            },
+            Some(b) => Err(self.unexpected(b)),
+            None => Err(Error::EndOfStream),
+        }
+    }
+
+    /// Reads a line that has to be valid UTF-8.
+    fn read_str_line(&mut self) -> Result<String> {
+        let offset = self.offset;
+        String::from_utf8(self.read_to_crlf()?)
+            .map_err(|source| Error::BadString { offset, source })
+    }
+
+    fn read_blob(&mut self, len: i64) -> Result<Vec<u8>> {
+        let mut buf: Vec<u8> = Vec::with_capacity((len as usize).min(PREALLOC_LIMIT));
+        self.read_exact_with(len as u64, |data| buf.extend_from_slice(data))?;
+        self.read_trailer()?;
+        Ok(buf)
+    }
+
+    /// Reads the CRLF that ends a bulk payload.
+    fn read_trailer(&mut self) -> Result<()> {
+        let start = self.offset;
+        match self.read_to_crlf()?.first() {
+            Some(&b) if !self.options.bulk_trailing_bytes => Err(Error::UnexpectedToken {
+                byte: b,
+                offset: start,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn extension_framing(&self, tag: u8) -> Option<extension::Framing> {
+        self.extensions.as_ref().and_then(|r| r.framing(tag))
+    }
+
+    fn parse_extension(&mut self, tag: u8) -> Result<RESPType> {
+        let framing = self.extension_framing(tag).ok_or(Error::UnknownError)?;
+        let offset = self.offset;
+        let payload = match framing {
+            extension::Framing::Line => self.read_to_crlf()?,
+            extension::Framing::Blob => match self.parse_integer()? {
+                n if n >= 0 => self.read_blob(n)?,
+                n => return Err(invalid_length(n, offset)),
+            },
+        };
+        let ext = Extension {
+            tag,
+            framing,
+            payload,
+        };
+        match &self.extensions {
+            Some(registry) => registry.decode(ext),
+            None => Err(Error::UnknownError),
+        }
+    }
+
+    fn parse_error(&mut self) -> Result<RESPType> {
+        Ok(RESPType::Error(self.read_status_line()?))
+    }
+
+    /// Reads the length line of a blob frame.
+    fn parse_integer(&mut self) -> Result<i64> {
+        let start = self.offset;
+        let line = self.read_status_line()?;
+        self.options.parse_int(&line, start)
+    }
+
+    fn parse_integer_reply(&mut self) -> Result<RESPType> {
+        let start = self.offset;
+        let line = self.read_str_line()?;
+        match self.options.parse_int(&line, start) {
+            Ok(i) => Ok(RESPType::Integer(i)),
+            Err(Error::BadInteger { .. }) if self.options.integer_overflow_big_number => {
+                let digits = line.trim_matches(|c| c == ' ' || c == '\t');
+                Ok(RESPType::BigNumber(
+                    digits.strip_prefix('+').unwrap_or(digits).to_string(),
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn parse_double(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        let line = self.read_str_line()?;
+        match line.parse::<f64>() {
+            Ok(d) => Ok(RESPType::Double(d)),
+            Err(_) => Err(Error::MalformedDouble {
+                value: line,
+                offset,
+            }),
+        }
+    }
+
+    fn parse_boolean(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        let line = self.read_to_crlf()?;
+        match &line[..] {
+            b"t" => Ok(RESPType::Boolean(true)),
+            b"f" => Ok(RESPType::Boolean(false)),
+            _ => Err(Error::MalformedBoolean {
+                value: String::from_utf8_lossy(&line).into_owned(),
+                offset,
+            }),
+        }
+    }
+
+    fn parse_null(&mut self) -> Result<RESPType> {
+        let start = self.offset;
+        match self.read_to_crlf()?.first() {
+            None => Ok(RESPType::Null),
+            Some(&b) => Err(Error::UnexpectedToken {
+                byte: b,
+                offset: start,
+            }),
+        }
+    }
+
+    fn parse_big_number(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        let line = self.read_str_line()?;
+        let digits = line.strip_prefix(['-', '+']).unwrap_or(&line);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::MalformedInteger {
+                value: line,
+                offset,
+            });
+        }
+        Ok(RESPType::BigNumber(line))
+    }
+
+    fn parse_bulk_error(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        match self.parse_integer()? {
+            n if n >= 0 => Ok(RESPType::BulkError(self.read_blob(n)?)),
+            n => Err(invalid_length(n, offset)),
+        }
+    }
+
+    fn parse_verbatim(&mut self) -> Result<RESPType> {
+        let offset = self.offset;
+        let (start, payload) = match self.parse_integer()? {
+            n if n >= 0 => (self.offset, self.read_blob(n)?),
+            n => return Err(invalid_length(n, offset)),
+        };
+        match payload.get(3) {
+            Some(b':') => Ok(RESPType::Verbatim {
+                format: [payload[0], payload[1], payload[2]],
+                text: payload[4..].to_vec(),
+            }),
+            _ => Err(Error::MalformedVerbatim {
+                value: String::from_utf8_lossy(&payload).into_owned(),
+                offset: start,
+            }),
+        }
+    }
+
+    fn parse_simple_str(&mut self) -> Result<RESPType> {
+        Ok(RESPType::SimpleString(self.read_status_line()?))
+    }
+
+    /// Reads a line as UTF-8, replacing invalid bytes if the options allow.
+    fn read_status_line(&mut self) -> Result<String> {
+        let offset = self.offset;
+        let buf = self.read_to_crlf()?;
+        let s = match String::from_utf8(buf) {
+            Ok(s) => s,
+            Err(err) if self.options.lossy_utf8 => {
+                String::from_utf8_lossy(err.as_bytes()).into_owned()
+            }
+            Err(source) => return Err(Error::BadString { offset, source }),
+        };
+        Ok(s)
+    }
+
+    /// Reads up to the next line feed and returns the line without its
+    /// CRLF.
+    fn read_to_crlf(&mut self) -> Result<Vec<u8>> {
+        let start = self.offset;
+        let mut buf: Vec<u8> = Vec::new();
+        loop {
+            let chunk = self.available()?;
+            if chunk.is_empty() {
+                return Err(Error::EndOfStream);
+            }
+            let (line, used, done) = match scan::find_byte(b'\n', chunk) {
+                Some(i) => (&chunk[..i], i + 1, true),
+                None => (chunk, chunk.len(), false),
+            };
+            buf.extend_from_slice(line);
+            self.consume(used);
+            if done {
+                break;
+            }
+        }
+        if self.options.loose_line_endings {
+            buf.retain(|&b| b != b'\r');
+            return Ok(buf);
+        }
+        match scan::find_byte(b'\r', &buf) {
+            Some(i) if i + 1 == buf.len() => {
+                buf.pop();
+                Ok(buf)
+            }
+            Some(i) => Err(Error::UnexpectedToken {
+                byte: b'\r',
+                offset: start + i as u64,
+            }),
+            None => Err(Error::UnexpectedToken {
+                byte: b'\n',
+                offset: start + buf.len() as u64,
+            }),
+        }
+    }
+}
+
+/// What [`Parser::parse_frame`] read: a whole value, or the header of an
+/// aggregate.
+enum Frame {
+    Value(RESPType),
+    Aggregate(Pending),
+}
+
+/// An aggregate whose elements are still being parsed. `remaining` is
+/// `None` for a streamed aggregate, which ends at a `.` marker.
+enum Pending {
+    Items {
+        kind: RESPKind,
+        items: Vec<RESPType>,
+        remaining: Option<i64>,
+    },
+    Entries {
+        attributes: bool,
+        entries: Vec<(RESPType, RESPType)>,
+        key: Option<RESPType>,
+        remaining: Option<i64>,
+    },
+    /// Attributes waiting for the value they apply to.
+    Attributed {
+        attrs: Vec<(RESPType, RESPType)>,
+        value: Option<RESPType>,
+    },
+}
+
+impl Pending {
+    fn push(&mut self, value: RESPType) {
+        match self {
+            Pending::Items {
+                items, remaining, ..
+            } => {
+                items.push(value);
+                if let Some(n) = remaining {
+                    *n -= 1;
+                }
+            }
+            Pending::Entries {
+                entries,
+                key,
+                remaining,
+                ..
+            } => match key.take() {
+                None => *key = Some(value),
+                Some(k) => {
+                    entries.push((k, value));
+                    if let Some(n) = remaining {
+                        *n -= 1;
+                    }
+                }
+            },
+            Pending::Attributed { value: slot, .. } => *slot = Some(value),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        match self {
+            Pending::Items { remaining, .. } => *remaining == Some(0),
+            Pending::Entries { key, remaining, .. } => key.is_none() && *remaining == Some(0),
+            Pending::Attributed { value, .. } => value.is_some(),
+        }
+    }
+
+    /// Whether a `.` read now ends this aggregate rather than starting an
+    /// element.
+    fn at_streamed_boundary(&self) -> bool {
+        match self {
+            Pending::Items { remaining, .. } => remaining.is_none(),
+            Pending::Entries { key, remaining, .. } => key.is_none() && remaining.is_none(),
+            Pending::Attributed { .. } => false,
+        }
+    }
+}
+
+fn pending_items(kind: RESPKind, len: Option<i64>, offset: u64) -> Result<Frame> {
+    match len {
+        Some(n) if n < 0 => Err(invalid_length(n, offset)),
+        remaining => Ok(Frame::Aggregate(Pending::Items {
+            kind,
+            items: Vec::new(),
+            remaining,
+        })),
+    }
+}
+
+fn invalid_length(length: i64, offset: u64) -> Error {
+    Error::InvalidLength { length, offset }
+}
+
+/// Finishes a complete aggregate. Attributes are pushed back onto `stack`
+/// to wait for their value, and `None` returned.
+fn close(stack: &mut Vec<Pending>, done: Pending) -> Option<RESPType> {
+    match done {
+        Pending::Items { kind, items, .. } => Some(match kind {
+            RESPKind::Set => RESPType::Set(items),
+            RESPKind::Push => RESPType::Push(items),
+            _ => RESPType::Array(items),
+        }),
+        Pending::Entries {
+            attributes: false,
+            entries,
+            ..
+        } => Some(RESPType::Map(entries)),
+        Pending::Entries { entries, .. } => {
+            stack.push(Pending::Attributed {
+                attrs: entries,
+                value: None,
+            });
+            None
+        }
+        Pending::Attributed { attrs, value } => Some(RESPType::Attributed {
+            attrs,
+            value: Box::new(value.expect("attributed value")),
+        }),
+    }
+}
+
+/// Iterator returned by [`Parser::frames`]. It yields `None` once the input
+/// ends between frames, and stops after the first error, since the parser
+/// cannot resynchronize with the stream.
+pub struct Frames<'a, R: io::Read> {
+    parser: &'a mut Parser<R>,
+    done: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Frames<'a, R> {
+    type Item = Result<RESPType>;
+
+    fn next(&mut self) -> Option<Result<RESPType>> {
+        if self.done {
+            return None;
+        }
+        match self.parser.parse_next() {
+            Ok(value) => Some(Ok(value)),
+            Err(Error::EndOfStream) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The result of [`Parser::stream_next`].
+pub enum Streaming<'a, R: io::Read> {
+    Value(RESPType),
+    Bulk(BulkReader<'a, R>),
+}
+
+/// Reads the payload of a large bulk string directly from the parser's
+/// input. It must be read to the end before the parser is used again;
+/// dropping it early leaves the parser partway through the frame.
+pub struct BulkReader<'a, R: io::Read> {
+    parser: &'a mut Parser<R>,
+    remaining: u64,
+    done: bool,
+}
+
+impl<'a, R: io::Read> BulkReader<'a, R> {
+    /// Number of payload bytes not read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl<'a, R: io::Read> io::Read for BulkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            if !self.done {
+                self.done = true;
+                self.parser.read_trailer().map_err(into_io_error)?;
+            }
+            return Ok(0);
+        }
+        let chunk = self.parser.available().map_err(into_io_error)?;
+        if chunk.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let n = (chunk.len() as u64).min(self.remaining) as usize;
+        let n = n.min(buf.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        self.parser.consume(n);
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn into_io_error(err: Error) -> io::Error {
+    match err {
+        Error::IoError(err) => err,
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+/// An item yielded by [`Parser::events`].
+///
+/// Every aggregate is reported as a `Start`, its elements, and an `End`; a
+/// map's elements alternate between keys and values. Attributes are also
+/// reported as an aggregate, followed by the value they apply to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The start of an aggregate of `len` elements, or of entries for maps
+    /// and attributes. `len` is `None` for a streamed aggregate.
+    Start { kind: RESPKind, len: Option<usize> },
+    /// A value that is not an aggregate.
+    Value(RESPType),
+    /// The end of the innermost open aggregate.
+    End,
+}
+
+/// An aggregate opened by a [`Event::Start`]. `remaining` counts frames,
+/// two per entry for maps, and is `None` when the aggregate is streamed.
+struct Level {
+    remaining: Option<u64>,
+    width: u64,
+    seen: u64,
+    attributes: bool,
+}
+
+/// Iterator returned by [`Parser::events`]. It ends at a clean end of input
+/// and stops after the first error.
+pub struct Events<'a, R: io::Read> {
+    parser: &'a mut Parser<R>,
+    stack: Vec<Level>,
+    done: bool,
+}
+
+impl<'a, R: io::Read> Events<'a, R> {
+    fn next_event(&mut self) -> Result<Option<Event>> {
+        if let Some(top) = self.stack.last() {
+            if top.remaining == Some(0) {
+                self.close();
+                return Ok(Some(Event::End));
+            }
+        }
+        let parser = &mut *self.parser;
+        let at_boundary = match self.stack.last() {
+            Some(top) => top.remaining.is_none() && top.seen % top.width == 0,
+            None => {
+                parser.frame_start = parser.offset;
+                false
+            }
+        };
+        let b = match parser.next_byte()? {
+            Some(b) => b,
+            None if self.stack.is_empty() => return Ok(None),
+            None => return Err(Error::Incomplete { needed: None }),
+        };
+        if at_boundary && b == b'.' {
+            parser.read_end_marker()?;
+            self.close();
+            return Ok(Some(Event::End));
+        }
+        let header = parser.offset - 1;
+        match parser.parse_frame(b, None)? {
+            Frame::Value(value) => {
+                self.count_element();
+                Ok(Some(Event::Value(value)))
+            }
+            Frame::Aggregate(pending) => {
+                if let Some(limit) = parser.options.max_depth {
+                    if self.stack.len() >= limit {
+                        return Err(Error::NestingTooDeep {
+                            limit,
+                            offset: header,
+                        });
+                    }
+                }
+                let (kind, len, width, attributes) = match pending {
+                    Pending::Items {
+                        kind, remaining, ..
+                    } => (kind, remaining, 1, false),
+                    Pending::Entries {
+                        attributes,
+                        remaining,
+                        ..
+                    } => {
+                        let kind = if attributes {
+                            RESPKind::Attributed
+                        } else {
+                            RESPKind::Map
+                        };
+                        (kind, remaining, 2, attributes)
+                    }
+                    Pending::Attributed { .. } => unreachable!("not read from a header"),
+                };
+                self.stack.push(Level {
+                    remaining: len.map(|n| n as u64 * width),
+                    width,
+                    seen: 0,
+                    attributes,
+                });
+                Ok(Some(Event::Start {
+                    kind,
+                    len: len.map(|n| n as usize),
+                }))
+            }
+        }
+    }
+
+    /// Pops the innermost aggregate, which counts as one element of its
+    /// parent unless it holds attributes.
+    fn close(&mut self) {
+        if let Some(level) = self.stack.pop() {
+            if !level.attributes {
+                self.count_element();
+            }
+        }
+    }
+
+    fn count_element(&mut self) {
+        if let Some(top) = self.stack.last_mut() {
+            top.seen += 1;
+            if let Some(n) = top.remaining.as_mut() {
+                *n -= 1;
+            }
+        }
+    }
+}
+
+impl<'a, R: io::Read> Iterator for Events<'a, R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        if self.done {
+            return None;
+        }
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                let err = match err {
+                    Error::EndOfStream => Error::Incomplete { needed: None },
+                    other => other,
+                };
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Parses `buf` as exactly one frame, failing with [`Error::TrailingBytes`]
+/// if anything follows it.
+pub fn parse_exact(buf: &[u8]) -> Result<RESPType> {
+    let mut values = parse_exact_n(buf, 1)?;
+    Ok(values.remove(0))
+}
+
+/// Parses `buf` as exactly `n` frames, failing with
+/// [`Error::TrailingBytes`] if anything follows them.
+pub fn parse_exact_n(buf: &[u8], n: usize) -> Result<Vec<RESPType>> {
+    let mut parser = Parser::buffered(buf);
+    // Every frame takes at least one byte, so `n` is not trusted further.
+    let mut values = Vec::with_capacity(n.min(buf.len()));
+    for _ in 0..n {
+        values.push(parser.parse_next()?);
+    }
+    let offset = parser.offset();
+    match buf.len() - offset as usize {
+        0 => Ok(values),
+        len => Err(Error::TrailingBytes { len, offset }),
+    }
+}
+
+/// Parses `buf` as exactly one frame. Equivalent to [`parse_exact`].
+pub fn from_slice(buf: &[u8]) -> Result<RESPType> {
+    parse_exact(buf)
+}
+
+/// Parses the whole of `reader` as exactly one frame, failing with
+/// [`Error::TrailingBytes`] if anything follows it. The reader is buffered
+/// internally.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<RESPType> {
+    Parser::buffered(reader).parse_complete()
+}
+
+#[cfg(test)]
+#[allow(clippy::char_lit_as_u8)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn do_parse(expr: &str) -> Result<RESPType> {
+        let mut parser = Parser::new(expr.as_bytes().bytes());
+        parser.parse_next()
+    }
+
+    fn do_parse_lenient(expr: &str) -> Result<RESPType> {
+        let mut parser = Parser::with_options(expr.as_bytes().bytes(), ParseOptions::lenient());
+        parser.parse_next()
+    }
+
+    macro_rules! test_parse_ok {
+        ($name:ident, $input:literal, $want:expr) => {
+            #[test]
+            fn $name() {
+                let have = do_parse($input);
+                match have {
+                    Ok(ref x) if *x == $want => assert!(true),
+                    _ => assert!(false),
+                }
+            }
+        };
+    }
+
+    macro_rules! test_parse_fail {
+        ($name:ident, $input:literal) => {
+            #[test]
+            fn $name() {
+                let have = do_parse($input);
+                match have {
+                    Err(_) => assert!(true),
+                    _ => assert!(false),
+                }
+            }
+        };
+    }
+
+    test_parse_ok!(valid_integer, ":32\r\n", RESPType::Integer(32));
+
+    test_parse_ok!(
+        valid_simple_string,
+        "+TEST\r\n",
+        RESPType::SimpleString("TEST".to_string())
+    );
+
+    test_parse_ok!(
+        valid_error,
+        "-ERROR\r\n",
+        RESPType::Error("ERROR".to_string())
+    );
+
+    test_parse_ok!(
+        valid_bulk_string,
+        "$5\r\nHE\rHE\r\n",
+        RESPType::BulkString(vec!('H' as u8, 'E' as u8, '\r' as u8, 'H' as u8, 'E' as u8))
+    );
+
+    test_parse_ok!(valid_null_bulk_string, "$-1\r\n", RESPType::Null);
+
+    test_parse_ok!(
+        valid_empty_bulk_string,
+        "$0\r\n\r\n",
+        RESPType::BulkString(Vec::new())
+    );
+
+    test_parse_ok!(
+        valid_array,
+        "*3\r\n:42\r\n+TEST\r\n$3\r\nXYZ\r\n\r\n",
+        RESPType::Array(vec!(
+            RESPType::Integer(42),
+            RESPType::SimpleString("TEST".to_string()),
+            RESPType::BulkString(vec!('X' as u8, 'Y' as u8, 'Z' as u8))
+        ))
+    );
+
+    test_parse_ok!(
+        valid_nested_array,
+        "*1\r\n*3\r\n:42\r\n+TEST\r\n$3\r\nXYZ\r\n\r\n\r\n",
+        RESPType::Array(vec!(RESPType::Array(vec!(
+            RESPType::Integer(42),
+            RESPType::SimpleString("TEST".to_string()),
+            RESPType::BulkString(vec!('X' as u8, 'Y' as u8, 'Z' as u8))
+        ))))
+    );
+
+    test_parse_ok!(valid_null_array, "*-1\r\n", RESPType::Null);
+
+    test_parse_ok!(valid_empty_array, "*0\r\n", RESPType::Array(Vec::new()));
+
+    test_parse_ok!(
+        parses_only_one_item,
+        ":32\r\n:42\r\n",
+        RESPType::Integer(32)
+    );
+
+    test_parse_fail!(empty_input, "");
+
+    test_parse_fail!(invalid_integer, ":ten\r\n");
+
+    test_parse_fail!(no_delimiter, ":10");
+
+    test_parse_fail!(bad_array, "*2\r\n+x\r\n\r\n");
+
+    test_parse_ok!(valid_zero, ":0\r\n", RESPType::Integer(0));
+
+    test_parse_ok!(valid_negative_zero, ":-0\r\n", RESPType::Integer(0));
+
+    test_parse_ok!(valid_negative_integer, ":-10\r\n", RESPType::Integer(-10));
+
+    test_parse_ok!(valid_double, ",3.25\r\n", RESPType::Double(3.25));
+
+    test_parse_ok!(
+        valid_double_exponent,
+        ",-1.5e3\r\n",
+        RESPType::Double(-1500.0)
+    );
+
+    test_parse_ok!(
+        valid_double_infinity,
+        ",-inf\r\n",
+        RESPType::Double(f64::NEG_INFINITY)
+    );
+
+    test_parse_fail!(invalid_double, ",1.2.3\r\n");
+
+    test_parse_ok!(valid_true, "#t\r\n", RESPType::Boolean(true));
+
+    test_parse_ok!(valid_false, "#f\r\n", RESPType::Boolean(false));
+
+    test_parse_fail!(invalid_boolean, "#x\r\n");
+
+    test_parse_ok!(valid_resp3_null, "_\r\n", RESPType::Null);
+
+    test_parse_fail!(invalid_resp3_null, "_x\r\n");
+
+    test_parse_ok!(
+        valid_big_number,
+        "(-3492890328409238509324850943850943825024385\r\n",
+        RESPType::BigNumber("-3492890328409238509324850943850943825024385".to_string())
+    );
+
+    test_parse_fail!(invalid_big_number, "(12a\r\n");
+
+    test_parse_ok!(
+        valid_map,
+        "%2\r\n+first\r\n:1\r\n+second\r\n%1\r\n$1\r\nk\r\n_\r\n",
+        RESPType::Map(vec![
+            (
+                RESPType::SimpleString("first".to_string()),
+                RESPType::Integer(1)
+            ),
+            (
+                RESPType::SimpleString("second".to_string()),
+                RESPType::Map(vec![(RESPType::BulkString(b"k".to_vec()), RESPType::Null)])
+            ),
+        ])
+    );
+
+    test_parse_ok!(
+        valid_set,
+        "~3\r\n+a\r\n*1\r\n:1\r\n~0\r\n",
+        RESPType::Set(vec![
+            RESPType::SimpleString("a".to_string()),
+            RESPType::Array(vec![RESPType::Integer(1)]),
+            RESPType::Set(Vec::new()),
+        ])
+    );
+
+    test_parse_ok!(
+        valid_attribute,
+        "|1\r\n+key-popularity\r\n%1\r\n$1\r\na\r\n,0.19\r\n*2\r\n:2039123\r\n:9543892\r\n",
+        RESPType::Attributed {
+            attrs: vec![(
+                RESPType::SimpleString("key-popularity".to_string()),
+                RESPType::Map(vec![(
+                    RESPType::BulkString(b"a".to_vec()),
+                    RESPType::Double(0.19)
+                )])
+            )],
+            value: Box::new(RESPType::Array(vec![
+                RESPType::Integer(2039123),
+                RESPType::Integer(9543892)
+            ]))
+        }
+    );
+
+    test_parse_ok!(
+        valid_nested_attribute,
+        "*2\r\n:1\r\n|1\r\n+ttl\r\n:3600\r\n:2\r\n",
+        RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::Attributed {
+                attrs: vec![(
+                    RESPType::SimpleString("ttl".to_string()),
+                    RESPType::Integer(3600)
+                )],
+                value: Box::new(RESPType::Integer(2))
+            }
+        ])
+    );
+
+    test_parse_fail!(attribute_without_value, "|1\r\n+a\r\n:1\r\n");
+
+    test_parse_ok!(
+        valid_push,
+        ">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n",
+        RESPType::Push(vec![
+            RESPType::BulkString(b"message".to_vec()),
+            RESPType::BulkString(b"news".to_vec()),
+            RESPType::BulkString(b"hi".to_vec()),
+        ])
+    );
+
+    test_parse_ok!(
+        valid_verbatim,
+        "=15\r\ntxt:Some string\r\n",
+        RESPType::Verbatim {
+            format: *b"txt",
+            text: b"Some string".to_vec()
+        }
+    );
+
+    test_parse_ok!(
+        valid_empty_verbatim,
+        "=4\r\nmkd:\r\n",
+        RESPType::Verbatim {
+            format: *b"mkd",
+            text: Vec::new()
+        }
+    );
+
+    test_parse_fail!(verbatim_without_format, "=3\r\ntxt\r\n");
+
+    test_parse_ok!(
+        valid_bulk_error,
+        "!21\r\nSYNTAX invalid syntax\r\n",
+        RESPType::BulkError(b"SYNTAX invalid syntax".to_vec())
+    );
+
+    test_parse_fail!(truncated_bulk_error, "!5\r\nab\r\n");
+
+    test_parse_ok!(
+        valid_streamed_string,
+        "$?\r\n;4\r\nHell\r\n;5\r\no wor\r\n;1\r\nd\r\n;0\r\n",
+        RESPType::BulkString(b"Hello word".to_vec())
+    );
+
+    test_parse_ok!(
+        empty_streamed_string,
+        "$?\r\n;0\r\n",
+        RESPType::BulkString(Vec::new())
+    );
+
+    test_parse_fail!(
+        streamed_string_without_chunk_marker,
+        "$?\r\n4\r\nHell\r\n;0\r\n"
+    );
+
+    test_parse_fail!(unterminated_streamed_string, "$?\r\n;2\r\nab\r\n");
+
+    test_parse_ok!(
+        valid_streamed_array,
+        "*?\r\n:1\r\n*?\r\n+a\r\n.\r\n.\r\n",
+        RESPType::Array(vec![
+            RESPType::Integer(1),
+            RESPType::Array(vec![RESPType::SimpleString("a".to_string())])
+        ])
+    );
+
+    test_parse_ok!(
+        valid_streamed_map,
+        "%?\r\n+a\r\n:1\r\n+b\r\n:2\r\n.\r\n",
+        RESPType::Map(vec![
+            (
+                RESPType::SimpleString("a".to_string()),
+                RESPType::Integer(1)
+            ),
+            (
+                RESPType::SimpleString("b".to_string()),
+                RESPType::Integer(2)
+            )
+        ])
+    );
+
+    test_parse_ok!(empty_streamed_set, "~?\r\n.\r\n", RESPType::Set(Vec::new()));
+
+    test_parse_fail!(unterminated_streamed_array, "*?\r\n:1\r\n");
+
+    test_parse_fail!(end_marker_in_map_value, "%?\r\n+a\r\n.\r\n");
+
+    test_parse_fail!(stray_end_marker, ".\r\n");
+
+    test_parse_fail!(bulk_string_longer_than_declared, "$1\r\nxy\r\n");
+
+    #[test]
+    fn lenient_bulk_trailing_bytes() {
+        assert_eq!(
+            do_parse_lenient("$1\r\nxy\r\n").unwrap(),
+            RESPType::BulkString(b"x".to_vec())
+        );
+    }
+
+    #[test]
+    fn bogus_length_does_not_preallocate() {
+        match do_parse("$9223372036854775807\r\nabc") {
+            Err(Error::Incomplete { needed: None }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    test_parse_fail!(truncated_map, "%2\r\n+a\r\n:1\r\n+b\r\n");
+
+    test_parse_fail!(negative_set, "~-1\r\n");
+
+    #[test]
+    fn chunked_descends_into_maps_and_sets() {
+        let mut parser = Parser::new(b"%1\r\n$1\r\nk\r\n~1\r\n$2\r\nvv\r\n".bytes());
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        let value = parser
+            .parse_next_chunked(|chunk| chunks.push(chunk.to_vec()))
+            .unwrap();
+        assert_eq!(
+            value,
+            RESPType::Map(vec![(
+                RESPType::BulkString(Vec::new()),
+                RESPType::Set(vec![RESPType::BulkString(Vec::new())])
+            )])
+        );
+        assert_eq!(chunks, vec![b"k".to_vec(), vec![], b"vv".to_vec(), vec![]]);
+    }
+
+    #[test]
+    fn parses_nan_double() {
+        match do_parse(",nan\r\n") {
+            Ok(RESPType::Double(d)) => assert!(d.is_nan()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    test_parse_fail!(strict_rejects_plus_sign, ":+5\r\n");
+
+    test_parse_fail!(strict_rejects_leading_zeros, ":007\r\n");
+
+    test_parse_fail!(strict_rejects_negative_leading_zeros, ":-07\r\n");
+
+    test_parse_fail!(strict_rejects_leading_space, ": 5\r\n");
+
+    test_parse_fail!(strict_rejects_trailing_space, ":5 \r\n");
+
+    test_parse_fail!(strict_rejects_bare_sign, ":-\r\n");
+
+    test_parse_fail!(strict_rejects_empty_integer, ":\r\n");
+
+    test_parse_fail!(strict_rejects_padded_length, "$03\r\nabc\r\n");
+
+    #[test]
+    fn lenient_integer_grammar() {
+        let cases = [
+            (":+5\r\n", 5),
+            (":007\r\n", 7),
+            (":-007\r\n", -7),
+            (": 5\r\n", 5),
+            (":\t-5 \r\n", -5),
+            (": +05 \r\n", 5),
+        ];
+        for (input, want) in cases.iter() {
+            match do_parse_lenient(input) {
+                Ok(RESPType::Integer(n)) => assert_eq!(n, *want, "input {:?}", input),
+                other => panic!("input {:?} gave {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn lenient_integer_rejects() {
+        for input in [":+\r\n", ":- 5\r\n", ":+-5\r\n", ":5 5\r\n", ":  \r\n"].iter() {
+            match do_parse_lenient(input) {
+                Err(Error::MalformedInteger { offset: 1, .. }) => {}
+                other => panic!("input {:?} gave {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn per_call_options_override_defaults() {
+        let input = ":+5\r\n:+5\r\n";
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        let have = parser.parse_next_with(&ParseOptions::lenient());
+        assert_eq!(have.unwrap(), RESPType::Integer(5));
+        assert!(parser.parse_next().is_err());
+        assert_eq!(parser.options(), &ParseOptions::strict());
+    }
+
+    /// Reader producing `count` copies of `frame` without holding them in
+    /// memory.
+    struct Repeat {
+        frame: &'static [u8],
+        pos: usize,
+        count: u64,
+    }
+
+    impl io::Read for Repeat {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut n = 0;
+            while n < buf.len() && self.count > 0 {
+                buf[n] = self.frame[self.pos];
+                n += 1;
+                self.pos += 1;
+                if self.pos == self.frame.len() {
+                    self.pos = 0;
+                    self.count -= 1;
+                }
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    #[ignore = "streams more than 4 GiB through the parser"]
+    fn offsets_do_not_wrap_past_4gib() {
+        let frame: &[u8] = b"$10\r\n0123456789\r\n";
+        let count = (u64::from(u32::MAX) / frame.len() as u64) + 2;
+        let reader = Repeat {
+            frame,
+            pos: 0,
+            count,
+        };
+        let mut parser = Parser::new(io::BufReader::new(reader).bytes());
+        let mut frames = 0u64;
+        while parser.parse_next().is_ok() {
+            frames += 1;
+        }
+        assert_eq!(frames, count);
+        assert_eq!(parser.offset(), count * frame.len() as u64);
+        assert!(parser.offset() > u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn per_call_frame_limit() {
+        let input = "$5\r\nhello\r\n$5\r\nhello\r\n";
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        match parser.parse_next_with_limit(8) {
+            Err(Error::FrameTooLarge {
+                limit: 8,
+                offset: 0,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(parser.options().max_frame_bytes, None);
+
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        assert!(parser.parse_next_with_limit(11).is_ok());
+        assert!(parser.parse_next_with_limit(10).is_err());
+    }
+
+    #[test]
+    fn parser_wide_frame_limit() {
+        let options = ParseOptions {
+            max_frame_bytes: Some(4),
+            ..ParseOptions::default()
+        };
+        let mut parser = Parser::with_options(":1\r\n:10\r\n".as_bytes().bytes(), options);
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(1));
+        assert!(parser.parse_next().is_err());
+    }
+
+    #[test]
+    fn parse_exact_rejects_trailing_bytes() {
+        assert_eq!(parse_exact(b":1\r\n").unwrap(), RESPType::Integer(1));
+        match parse_exact(b":1\r\n:2\r\n") {
+            Err(Error::TrailingBytes { len: 4, offset: 4 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_exact(b"").is_err());
+    }
+
+    #[test]
+    fn parse_exact_n_requires_all_frames() {
+        let values = parse_exact_n(b":1\r\n+OK\r\n", 2).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                RESPType::Integer(1),
+                RESPType::SimpleString("OK".to_string())
+            ]
+        );
+        assert!(parse_exact_n(b":1\r\n", 2).is_err());
+        assert!(parse_exact_n(b":1\r\n+OK\r\n\r\n", 2).is_err());
+        assert_eq!(parse_exact_n(b"", 0).unwrap(), vec![]);
+        assert!(parse_exact_n(b"", usize::MAX).is_err());
+    }
+
+    #[test]
+    fn integer_overflow_is_bad_integer() {
+        match do_parse(":9223372036854775808\r\n") {
+            Err(Error::BadInteger { offset: 1, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn chunked_delivers_payloads_in_bounded_pieces() {
+        let payload = vec![b'x'; CHUNK_SIZE * 2 + 5];
+        let mut input = b"*3\r\n:1\r\n".to_vec();
+        input.extend(format!("${}\r\n", payload.len()).as_bytes());
+        input.extend(&payload);
+        input.extend(b"\r\n$2\r\nab\r\n");
+        let mut parser = Parser::new(input.bytes());
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        let value = parser
+            .parse_next_chunked(|chunk| chunks.push(chunk.to_vec()))
+            .unwrap();
+        assert_eq!(
+            value,
+            RESPType::Array(vec![
+                RESPType::Integer(1),
+                RESPType::BulkString(Vec::new()),
+                RESPType::BulkString(Vec::new()),
+            ])
+        );
+        let sizes: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![CHUNK_SIZE, CHUNK_SIZE, 5, 0, 2, 0]);
+        assert_eq!(chunks[4], b"ab".to_vec());
+        assert_eq!(parser.offset(), input.len() as u64);
+    }
+
+    #[test]
+    fn chunked_streams_streamed_strings() {
+        let mut parser = Parser::new(b"$?\r\n;2\r\nab\r\n;1\r\nc\r\n;0\r\n".bytes());
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        let value = parser
+            .parse_next_chunked(|chunk| chunks.push(chunk.to_vec()))
+            .unwrap();
+        assert_eq!(value, RESPType::BulkString(Vec::new()));
+        assert_eq!(chunks, vec![b"ab".to_vec(), b"c".to_vec(), Vec::new()]);
+    }
+
+    #[test]
+    fn resp2_mode_rejects_resp3_frames() {
+        let options = ParseOptions {
+            protocol: ProtocolVersion::Resp2,
+            ..ParseOptions::default()
+        };
+        let mut parser = Parser::with_options(b"*2\r\n:1\r\n$-1\r\n".bytes(), options.clone());
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::Array(vec![RESPType::Integer(1), RESPType::Null])
+        );
+        for input in &[
+            "*1\r\n%0\r\n",
+            "_\r\n",
+            "#t\r\n",
+            "$?\r\n;0\r\n",
+            "*?\r\n.\r\n",
+        ] {
+            let mut parser = Parser::with_options(input.as_bytes().bytes(), options.clone());
+            assert!(parser.parse_next().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn switches_protocol_between_frames() {
+        let mut parser = Parser::new(b"_\r\n_\r\n".bytes());
+        assert_eq!(parser.protocol(), ProtocolVersion::Resp3);
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Null);
+        parser.set_protocol(ProtocolVersion::Resp2);
+        match parser.parse_next() {
+            Err(Error::UnexpectedToken {
+                byte: b'_',
+                offset: 3,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn distinguishes_truncated_frames_from_end_of_stream() {
+        let mut parser = Parser::new(b":1\r\n*2\r\n:1".bytes());
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(1));
+        match parser.parse_next() {
+            Err(Error::Incomplete { needed: None }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match do_parse("") {
+            Err(Error::EndOfStream) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors_carry_stream_offsets() {
+        let mut parser = Parser::new(b":1\r\n*2\r\n:2\r\n?x\r\n".bytes());
+        parser.parse_next().unwrap();
+        match parser.parse_next() {
+            Err(Error::UnexpectedToken {
+                byte: b'?',
+                offset: 12,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match do_parse("$3\r\nabcd\r\n") {
+            Err(Error::UnexpectedToken {
+                byte: b'd',
+                offset: 7,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match do_parse("*1\r\n$99999999999999999999\r\n") {
+            Err(err @ Error::BadInteger { offset: 5, .. }) => {
+                assert!(err.to_string().starts_with("Bad integer at offset 5"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overflowing_integers_as_big_numbers() {
+        let options = ParseOptions {
+            integer_overflow_big_number: true,
+            ..ParseOptions::lenient()
+        };
+        let input =
+            b":340282366920938463463374607431768211455\r\n:+18446744073709551616\r\n:-5\r\n";
+        let mut parser = Parser::with_options(input.bytes(), options);
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::BigNumber("340282366920938463463374607431768211455".to_string())
+        );
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::BigNumber("18446744073709551616".to_string())
+        );
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(-5));
+        assert!(do_parse(":340282366920938463463374607431768211455\r\n").is_err());
+    }
+
+    #[test]
+    fn lossy_utf8_keeps_status_lines() {
+        let input = b"-ERR bad \xff\r\n";
+        assert!(matches!(
+            Parser::new(input.bytes()).parse_next(),
+            Err(Error::BadString { offset: 1, .. })
+        ));
+        let options = ParseOptions {
+            lossy_utf8: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            Parser::with_options(input.bytes(), options)
+                .parse_next()
+                .unwrap(),
+            RESPType::Error("ERR bad \u{fffd}".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        assert_eq!(do_parse("*-1\r\n").unwrap(), RESPType::Null);
+        for (input, n, at) in [
+            ("$-2\r\n", -2, 1),
+            ("*-5\r\n", -5, 1),
+            ("%-1\r\n", -1, 1),
+            ("~-3\r\n", -3, 1),
+            ("!-1\r\n", -1, 1),
+            ("$?\r\n;-1\r\n", -1, 5),
+        ] {
+            match do_parse(input) {
+                Err(Error::InvalidLength { length, offset }) if length == n && offset == at => {}
+                other => panic!("unexpected {:?} for {:?}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn requires_crlf_line_endings() {
+        match do_parse("+OK\n") {
+            Err(Error::UnexpectedToken {
+                byte: b'\n',
+                offset: 3,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match do_parse("+a\rb\r\n") {
+            Err(Error::UnexpectedToken {
+                byte: b'\r',
+                offset: 2,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(do_parse("$1\r\nx\n").is_err());
+        assert_eq!(
+            do_parse_lenient("*1\n+a\rb\r\n").unwrap(),
+            RESPType::Array(vec![RESPType::SimpleString("ab".to_string())])
+        );
+    }
+
+    #[test]
+    fn returns_raw_frame_bytes() {
+        let mut parser = Parser::buffered(&b"*1\r\n:+1\r\n$2\r\nab\r\n"[..]);
+        assert!(parser.parse_next_raw().is_err());
+        assert_eq!(parser.last_frame_bytes(), None);
+
+        let mut parser = Parser::buffered_with_options(
+            &b"*1\r\n:+1\r\n$2\r\nab\r\n"[..],
+            ParseOptions::lenient(),
+        );
+        let (value, raw) = parser.parse_next_raw().unwrap();
+        assert_eq!(value, RESPType::Array(vec![RESPType::Integer(1)]));
+        assert_eq!(raw, b"*1\r\n:+1\r\n".to_vec());
+        parser.set_capture_frames(true);
+        let (_, raw) = parser.parse_next_raw().unwrap();
+        assert_eq!(raw, b"$2\r\nab\r\n".to_vec());
+        assert_eq!(parser.last_frame_bytes(), Some(&raw[..]));
+    }
+
+    #[test]
+    fn streams_large_bulk_strings() {
+        let payload = vec![b'x'; 3 * DEFAULT_BUF_SIZE];
+        let mut input = format!("${}\r\n", payload.len()).into_bytes();
+        input.extend(&payload);
+        input.extend(b"\r\n$3\r\nabc\r\n:1\r\n");
+        let mut parser = Parser::buffered(Trickle(&input));
+        match parser.stream_next(1024).unwrap() {
+            Streaming::Bulk(mut reader) => {
+                assert_eq!(reader.remaining(), payload.len() as u64);
+                let mut have = Vec::new();
+                io::copy(&mut reader, &mut have).unwrap();
+                assert_eq!(have, payload);
+            }
+            Streaming::Value(value) => panic!("unexpected {:?}", value),
+        }
+        match parser.stream_next(1024).unwrap() {
+            Streaming::Value(value) => assert_eq!(value, RESPType::BulkString(b"abc".to_vec())),
+            Streaming::Bulk(_) => panic!("small payload streamed"),
+        }
+        match parser.stream_next(1024).unwrap() {
+            Streaming::Value(value) => assert_eq!(value, RESPType::Integer(1)),
+            Streaming::Bulk(_) => panic!("integer streamed"),
+        }
+    }
+
+    #[test]
+    fn bulk_reader_reports_truncation() {
+        let mut parser = Parser::new(b"$10\r\nabc".bytes());
+        match parser.stream_next(0).unwrap() {
+            Streaming::Bulk(mut reader) => {
+                let mut have = Vec::new();
+                let err = reader.read_to_end(&mut have).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+                assert_eq!(have, b"abc".to_vec());
+            }
+            Streaming::Value(value) => panic!("unexpected {:?}", value),
+        }
+    }
+
+    #[test]
+    fn emits_events() {
+        let input = b"*2\r\n$1\r\na\r\n%?\r\n+k\r\n~0\r\n.\r\n|1\r\n+t\r\n:1\r\n:2\r\n";
+        let mut parser = Parser::new(input.bytes());
+        let events: Vec<Event> = parser.events().collect::<Result<_>>().unwrap();
+        let start = |kind, len| Event::Start { kind, len };
+        assert_eq!(
+            events,
+            vec![
+                start(RESPKind::Array, Some(2)),
+                Event::Value(RESPType::BulkString(b"a".to_vec())),
+                start(RESPKind::Map, None),
+                Event::Value(RESPType::SimpleString("k".to_string())),
+                start(RESPKind::Set, Some(0)),
+                Event::End,
+                Event::End,
+                Event::End,
+                start(RESPKind::Attributed, Some(1)),
+                Event::Value(RESPType::SimpleString("t".to_string())),
+                Event::Value(RESPType::Integer(1)),
+                Event::End,
+                Event::Value(RESPType::Integer(2)),
+            ]
+        );
+
+        let mut parser = Parser::new(b"*3\r\n:1\r\n".bytes());
+        let mut events = parser.events();
+        events.next().unwrap().unwrap();
+        events.next().unwrap().unwrap();
+        assert!(matches!(
+            events.next(),
+            Some(Err(Error::Incomplete { needed: None }))
+        ));
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn skips_frames() {
+        let input: &[u8] =
+            b"*3\r\n$3\r\nabc\r\n%1\r\n+k\r\n*?\r\n:1\r\n.\r\n|1\r\n+a\r\n+b\r\n:2\r\n\
+            $?\r\n;2\r\nxy\r\n;0\r\n*-1\r\n%?\r\n+k\r\n~?\r\n.\r\n.\r\n=7\r\ntxt:abc\r\n+OK\r\n";
+        let mut parser = Parser::buffered(input);
+        parser.set_capture_frames(true);
+        assert_eq!(parser.skip_next().unwrap(), RESPKind::Array);
+        assert_eq!(parser.last_frame_bytes().unwrap().len(), 48);
+        assert_eq!(parser.skip_next().unwrap(), RESPKind::BulkString);
+        assert_eq!(parser.skip_next().unwrap(), RESPKind::Null);
+        assert_eq!(parser.skip_next().unwrap(), RESPKind::Map);
+        assert_eq!(parser.skip_next().unwrap(), RESPKind::Verbatim);
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::SimpleString("OK".to_string())
+        );
+        assert!(matches!(parser.skip_next(), Err(Error::EndOfStream)));
+
+        let mut parser = Parser::new(b"*2\r\n$100\r\nabc".bytes());
+        assert!(matches!(
+            parser.skip_next(),
+            Err(Error::Incomplete { needed: None })
+        ));
+    }
+
+    #[test]
+    fn skip_rejects_overflowing_lengths() {
+        let max = i64::MAX;
+        for input in &[
+            format!("%{}\r\n%{}\r\n", max, max),
+            format!("*{}\r\n*{}\r\n*{}\r\n", max, max, max),
+        ] {
+            match Parser::buffered(input.as_bytes()).skip_next() {
+                Err(Error::InvalidLength { length, .. }) => assert_eq!(length, max),
+                other => panic!("unexpected {:?} for {:?}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn deep_nesting_does_not_recurse() {
+        let deep = "*1\r\n".repeat(1_000_000);
+        let options = ParseOptions {
+            max_depth: None,
+            ..ParseOptions::default()
+        };
+        match Parser::buffered_with_options(deep.as_bytes(), options).parse_next() {
+            Err(Error::Incomplete { needed: None }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let input = format!(
+            "{}:1\r\n",
+            "*1\r\n|1\r\n+k\r\n+v\r\n%?\r\n+k\r\n".repeat(100)
+        );
+        let input = input + &".\r\n".repeat(100);
+        let mut value = Parser::buffered(input.as_bytes()).parse_next().unwrap();
+        for _ in 0..100 {
+            value = match value {
+                RESPType::Array(mut items) if items.len() == 1 => match items.remove(0) {
+                    RESPType::Attributed { value, .. } => match *value {
+                        RESPType::Map(mut entries) if entries.len() == 1 => entries.remove(0).1,
+                        other => panic!("unexpected {:?}", other),
+                    },
+                    other => panic!("unexpected {:?}", other),
+                },
+                other => panic!("unexpected {:?}", other),
+            };
+        }
+        assert_eq!(value, RESPType::Integer(1));
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let options = ParseOptions {
+            max_depth: Some(2),
+            ..ParseOptions::default()
+        };
+        let mut parser = Parser::with_options(b"*1\r\n%1\r\n:1\r\n:2\r\n".bytes(), options.clone());
+        assert!(parser.parse_next().is_ok());
+        let mut parser = Parser::with_options(b"*1\r\n*1\r\n*0\r\n:1\r\n".bytes(), options);
+        match parser.parse_next() {
+            Err(Error::NestingTooDeep {
+                limit: 2,
+                offset: 8,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let deep = "*1\r\n".repeat(100_000);
+        let options = ParseOptions {
+            max_depth: Some(64),
+            ..ParseOptions::default()
+        };
+        let mut parser = Parser::buffered_with_options(deep.as_bytes(), options);
+        match parser.parse_next() {
+            Err(Error::NestingTooDeep {
+                limit: 64,
+                offset: 256,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_depth_limit_keeps_values_droppable() {
+        let frame = |depth: usize| format!("{}:1\r\n", "*1\r\n".repeat(depth));
+        let value = Parser::buffered(frame(DEFAULT_MAX_DEPTH).as_bytes())
+            .parse_next()
+            .unwrap();
+        let copy = value.clone();
+        assert_eq!(copy, value);
+        assert!(!value.to_string().is_empty());
+        assert_eq!(
+            crate::to_vec(&value).unwrap(),
+            frame(DEFAULT_MAX_DEPTH).into_bytes()
+        );
+        drop(copy);
+        drop(value);
+
+        let deep = frame(1_000_000);
+        match Parser::buffered(deep.as_bytes()).parse_next() {
+            Err(Error::NestingTooDeep { limit, offset }) => {
+                assert_eq!(limit, DEFAULT_MAX_DEPTH);
+                assert_eq!(offset, 4 * DEFAULT_MAX_DEPTH as u64);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_complete_rejects_trailing_input() {
+        let mut parser = Parser::new(b":1\r\n".bytes());
+        assert_eq!(parser.parse_complete().unwrap(), RESPType::Integer(1));
+        let mut parser = Parser::new(b":1\r\n:2\r\n".bytes());
+        match parser.parse_complete() {
+            Err(Error::TrailingBytes { len: 4, offset: 4 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn into_inner_returns_unparsed_bytes() {
+        let mut parser = Parser::buffered(Trickle(b"+FULLRESYNC\r\n$3\r\nabc"));
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::SimpleString("FULLRESYNC".to_string())
+        );
+        let (mut reader, buffered) = parser.into_inner().unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!([buffered, rest].concat(), b"$3\r\nabc".to_vec());
+
+        assert!(Parser::new(b"".bytes()).into_inner().is_none());
+    }
+
+    #[test]
+    fn iterates_over_frames() {
+        let mut parser = Parser::buffered(&b"+OK\r\n:1\r\n$1\r\nx\r\n"[..]);
+        let frames: Result<Vec<RESPType>> = parser.frames().collect();
+        assert_eq!(
+            frames.unwrap(),
+            vec![
+                RESPType::SimpleString("OK".to_string()),
+                RESPType::Integer(1),
+                RESPType::BulkString(b"x".to_vec()),
+            ]
+        );
+
+        let mut parser = Parser::new(b":1\r\n:x\r\n:2\r\n".bytes());
+        let mut frames = parser.frames();
+        assert_eq!(frames.next().unwrap().unwrap(), RESPType::Integer(1));
+        assert!(frames.next().unwrap().is_err());
+        assert!(frames.next().is_none());
+    }
+
+    /// Returns at most three bytes per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> io::Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn buffered_parser_matches_byte_parser() {
+        let input = crate::testgen::Generator::new(7)
+            .max_bytes(DEFAULT_BUF_SIZE + 100)
+            .stream(50);
+        let mut bytes = Parser::new(input.bytes());
+        let mut buffered = Parser::buffered(&input[..]);
+        let mut trickle = Parser::buffered(Trickle(&input));
+        let mut small = Parser::buffered_with_capacity(&input[..], 0, ParseOptions::default());
+        for _ in 0..50 {
+            let want = bytes.parse_next().unwrap();
+            assert_eq!(buffered.parse_next().unwrap(), want);
+            assert_eq!(trickle.parse_next().unwrap(), want);
+            assert_eq!(small.parse_next().unwrap(), want);
+            assert_eq!(buffered.offset(), bytes.offset());
+        }
+        assert!(buffered.parse_next().is_err());
+    }
+
+    #[test]
+    fn buffered_parser_enforces_frame_limit() {
+        let options = ParseOptions {
+            max_frame_bytes: Some(9),
+            ..ParseOptions::default()
+        };
+        let mut parser =
+            Parser::buffered_with_options(&b"$3\r\nabc\r\n$4\r\nabcd\r\n"[..], options);
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::BulkString(b"abc".to_vec())
+        );
+        match parser.parse_next() {
+            Err(Error::FrameTooLarge {
+                limit: 9,
+                offset: 9,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(parser.offset(), 18);
+    }
+
+    #[test]
+    fn captures_raw_frames() {
+        let input = b"*2\r\n:+1\r\n$1\nx\r\n+OK\r\n:x";
+        let mut parser = Parser::with_options(input.bytes(), ParseOptions::lenient());
+        assert_eq!(parser.last_frame_bytes(), None);
+        parser.set_capture_frames(true);
+        parser.parse_next().unwrap();
+        assert_eq!(
+            parser.last_frame_bytes(),
+            Some(&b"*2\r\n:+1\r\n$1\nx\r\n"[..])
+        );
+        parser.parse_next().unwrap();
+        assert_eq!(parser.last_frame_bytes(), Some(&b"+OK\r\n"[..]));
+        assert!(parser.parse_next().is_err());
+        assert_eq!(parser.last_frame_bytes(), Some(&b":x"[..]));
+        parser.set_capture_frames(false);
+        assert_eq!(parser.last_frame_bytes(), None);
+    }
+
+    #[test]
+    fn from_reader_consumes_whole_input() {
+        assert_eq!(
+            from_reader(&b"+OK\r\n"[..]).unwrap(),
+            RESPType::SimpleString("OK".to_string())
+        );
+        match from_reader(&b"+OK\r\n:1\r\n"[..]) {
+            Err(Error::TrailingBytes { len: 4, offset: 5 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(from_slice(b":1\r\n").unwrap(), RESPType::Integer(1));
+    }
+}
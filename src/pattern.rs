@@ -0,0 +1,105 @@
+//! Redis-style glob matching, as used by KEYS, SCAN MATCH and PSUBSCRIBE.
+
+/// Reports whether `subject` matches the glob `pattern`.
+///
+/// `*` matches any sequence of bytes, `?` any single byte, `[abc]`, `[a-z]`
+/// and `[^abc]` a byte from (or not from) a set, and `\` escapes the next
+/// byte. As in Redis, an unterminated `[` extends to the end of the pattern
+/// and a trailing `\` matches itself.
+pub fn matches<P: AsRef<[u8]>, S: AsRef<[u8]>>(pattern: P, subject: S) -> bool {
+    let (pat, subj) = (pattern.as_ref(), subject.as_ref());
+    let (mut p, mut s) = (0, 0);
+    // Where to resume after the most recent `*`: the pattern position just
+    // past it and the subject position it is currently assumed to reach.
+    let mut star: Option<(usize, usize)> = None;
+    while s < subj.len() {
+        if p < pat.len() && pat[p] == b'*' {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+        if p < pat.len() {
+            if let Some(next) = match_token(pat, p, subj[s]) {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+        match star {
+            Some((sp, ss)) => {
+                p = sp;
+                s = ss + 1;
+                star = Some((sp, s));
+            }
+            None => return false,
+        }
+    }
+    pat[p..].iter().all(|&b| b == b'*')
+}
+
+/// Matches the single-byte token at `pat[p]` against `c`, returning the
+/// position of the following token on success.
+fn match_token(pat: &[u8], p: usize, c: u8) -> Option<usize> {
+    match pat[p] {
+        b'?' => Some(p + 1),
+        b'\\' if p + 1 < pat.len() => (pat[p + 1] == c).then_some(p + 2),
+        b'[' => match_class(pat, p + 1, c),
+        b => (b == c).then_some(p + 1),
+    }
+}
+
+fn match_class(pat: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pat.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+    let mut found = false;
+    while p < pat.len() && pat[p] != b']' {
+        if pat[p] == b'\\' && p + 1 < pat.len() {
+            found |= pat[p + 1] == c;
+            p += 2;
+        } else if p + 2 < pat.len() && pat[p + 1] == b'-' && pat[p + 2] != b']' {
+            let (lo, hi) = (pat[p].min(pat[p + 2]), pat[p].max(pat[p + 2]));
+            found |= lo <= c && c <= hi;
+            p += 3;
+        } else {
+            found |= pat[p] == c;
+            p += 1;
+        }
+    }
+    (found != negate).then_some((p + 1).min(pat.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("*", ""));
+        assert!(matches("news.*", "news.tech"));
+        assert!(matches("h?llo", "hello"));
+        assert!(!matches("h?llo", "hllo"));
+        assert!(matches("h*llo", "heeeello"));
+        assert!(matches("*a*b*", "xxaxxbxx"));
+        assert!(!matches("*a*b", "xxaxxbxx"));
+        assert!(matches("a**", "a"));
+        assert!(!matches("abc", "abcd"));
+    }
+
+    #[test]
+    fn matches_classes_and_escapes() {
+        assert!(matches("h[ae]llo", "hallo"));
+        assert!(!matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo"));
+        assert!(!matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-b]llo", "hbllo"));
+        assert!(matches("h[b-a]llo", "hallo"));
+        assert!(matches("[\\]]", "]"));
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+        assert!(matches("a\\", "a\\"));
+        assert!(matches("[ab", "b"));
+        assert!(matches(b"k\xff?", b"k\xff\x00"));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{Parser, RESPType, Result};
+use std::collections::VecDeque;
+use std::io;
+
+/// Wraps a [`Parser`] so that upcoming frames can be inspected before they
+/// are consumed, e.g. to see whether a push frame precedes a reply.
+pub struct PeekableParser<R: io::Read> {
+    parser: Parser<R>,
+    buffered: VecDeque<RESPType>,
+}
+
+impl<R: io::Read> PeekableParser<R> {
+    pub fn new(parser: Parser<R>) -> PeekableParser<R> {
+        PeekableParser {
+            parser,
+            buffered: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next frame, from the look-ahead buffer if one is there.
+    pub fn parse_next(&mut self) -> Result<RESPType> {
+        match self.buffered.pop_front() {
+            Some(value) => Ok(value),
+            None => self.parser.parse_next(),
+        }
+    }
+
+    pub fn peek(&mut self) -> Result<&RESPType> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the frame `n` positions ahead without consuming it, parsing
+    /// and buffering the frames up to it as needed. Frames buffered before
+    /// a parse error stay available.
+    pub fn peek_nth(&mut self, n: usize) -> Result<&RESPType> {
+        while self.buffered.len() <= n {
+            let value = self.parser.parse_next()?;
+            self.buffered.push_back(value);
+        }
+        Ok(&self.buffered[n])
+    }
+
+    /// Number of frames parsed but not yet consumed.
+    pub fn buffered(&self) -> usize {
+        self.buffered.len()
+    }
+
+    pub fn get_ref(&self) -> &Parser<R> {
+        &self.parser
+    }
+
+    /// Returns the wrapped parser along with any frames still buffered.
+    pub fn into_parts(self) -> (Parser<R>, Vec<RESPType>) {
+        (self.parser, self.buffered.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn peeks_without_consuming() {
+        let mut parser = PeekableParser::new(Parser::new(b":1\r\n+OK\r\n:2\r\n".bytes()));
+        assert_eq!(
+            parser.peek_nth(1).unwrap(),
+            &RESPType::SimpleString("OK".to_string())
+        );
+        assert_eq!(parser.buffered(), 2);
+        assert_eq!(parser.peek().unwrap(), &RESPType::Integer(1));
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(1));
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::SimpleString("OK".to_string())
+        );
+        assert_eq!(parser.buffered(), 0);
+        assert_eq!(parser.parse_next().unwrap(), RESPType::Integer(2));
+        assert!(parser.peek().is_err());
+    }
+
+    #[test]
+    fn keeps_frames_buffered_before_error() {
+        let mut parser = PeekableParser::new(Parser::new(b":1\r\n?".bytes()));
+        assert!(parser.peek_nth(1).is_err());
+        assert_eq!(parser.buffered(), 1);
+        let (_, rest) = parser.into_parts();
+        assert_eq!(rest, vec![RESPType::Integer(1)]);
+    }
+}
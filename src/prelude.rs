@@ -0,0 +1,7 @@
+//! Glob-importable set of the most commonly used items:
+//! `use resp_rs::prelude::*;`
+
+pub use crate::convert::{FromResp, ToResp};
+pub use crate::error::{Error, Result};
+pub use crate::parse::{ParseOptions, Parser, ProtocolVersion};
+pub use crate::value::{RESPKind, RESPType};
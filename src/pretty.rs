@@ -0,0 +1,365 @@
+use crate::escape;
+use crate::RESPType;
+use std::fmt;
+
+/// Renders values as an indented tree, one frame per line, using the RESP
+/// type markers as prefixes (`+`, `-`, `:`, `$`, `*`, `%`, `~`, `>`, `,`,
+/// `#`, `(`, `=`, `!`, `|`). Map values are indented below their keys, and
+/// an attributed value follows its attributes at the same level.
+///
+/// Large bulk strings and arrays can be truncated so that the output stays
+/// usable in logs.
+#[derive(Debug, Clone)]
+pub struct PrettyPrinter {
+    indent: usize,
+    max_bytes: Option<usize>,
+    max_elements: Option<usize>,
+    #[cfg(feature = "cli")]
+    color: bool,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> PrettyPrinter {
+        PrettyPrinter::new()
+    }
+}
+
+#[cfg(feature = "cli")]
+const RESET: &str = "\x1b[0m";
+
+impl PrettyPrinter {
+    pub fn new() -> PrettyPrinter {
+        PrettyPrinter {
+            indent: 2,
+            max_bytes: None,
+            max_elements: None,
+            #[cfg(feature = "cli")]
+            color: false,
+        }
+    }
+
+    /// Number of spaces per nesting level.
+    pub fn indent(mut self, width: usize) -> PrettyPrinter {
+        self.indent = width;
+        self
+    }
+
+    /// Maximum number of payload bytes shown for each bulk string.
+    pub fn max_bytes(mut self, n: usize) -> PrettyPrinter {
+        self.max_bytes = Some(n);
+        self
+    }
+
+    /// Maximum number of elements shown for each array.
+    pub fn max_elements(mut self, n: usize) -> PrettyPrinter {
+        self.max_elements = Some(n);
+        self
+    }
+
+    /// Highlight values with ANSI colors.
+    #[cfg(feature = "cli")]
+    pub fn color(mut self, enabled: bool) -> PrettyPrinter {
+        self.color = enabled;
+        self
+    }
+
+    /// Wraps `value` so that it can be used with `format!` and friends.
+    pub fn display<'a>(&'a self, value: &'a RESPType) -> Pretty<'a> {
+        Pretty {
+            printer: self,
+            value,
+        }
+    }
+
+    pub fn print(&self, value: &RESPType) -> String {
+        self.display(value).to_string()
+    }
+
+    pub fn write<W: fmt::Write>(&self, w: &mut W, value: &RESPType) -> fmt::Result {
+        self.write_value(w, value, 0)
+    }
+
+    fn write_value<W: fmt::Write>(&self, w: &mut W, value: &RESPType, depth: usize) -> fmt::Result {
+        for _ in 0..depth * self.indent {
+            w.write_char(' ')?;
+        }
+        match value {
+            RESPType::SimpleString(s) => {
+                self.start(w, "32")?;
+                w.write_char('+')?;
+                write_quoted(w, s.as_bytes())?;
+                self.end(w)
+            }
+            RESPType::Error(s) => {
+                self.start(w, "31")?;
+                w.write_char('-')?;
+                write_quoted(w, s.as_bytes())?;
+                self.end(w)
+            }
+            RESPType::Integer(i) => {
+                self.start(w, "36")?;
+                w.write_fmt(format_args!(":{}", i))?;
+                self.end(w)
+            }
+            RESPType::Double(d) => {
+                self.start(w, "36")?;
+                w.write_fmt(format_args!(",{}", d))?;
+                self.end(w)
+            }
+            RESPType::Boolean(b) => {
+                self.start(w, "36")?;
+                w.write_str(if *b { "#t" } else { "#f" })?;
+                self.end(w)
+            }
+            RESPType::BigNumber(n) => {
+                self.start(w, "36")?;
+                w.write_fmt(format_args!("({}", n))?;
+                self.end(w)
+            }
+            RESPType::BulkString(buf) => {
+                let shown = match self.max_bytes {
+                    Some(n) if buf.len() > n => &buf[..n],
+                    _ => &buf[..],
+                };
+                self.start(w, "33")?;
+                w.write_fmt(format_args!("${} ", buf.len()))?;
+                write_quoted(w, shown)?;
+                self.end(w)?;
+                if shown.len() < buf.len() {
+                    w.write_fmt(format_args!(" (+{} bytes)", buf.len() - shown.len()))?;
+                }
+                Ok(())
+            }
+            RESPType::BulkError(buf) => {
+                self.start(w, "31")?;
+                w.write_fmt(format_args!("!{} ", buf.len()))?;
+                write_quoted(w, buf)?;
+                self.end(w)
+            }
+            RESPType::Verbatim { format, text } => {
+                self.start(w, "33")?;
+                w.write_char('=')?;
+                escape::write_escaped(w, format)?;
+                w.write_char(' ')?;
+                write_quoted(w, text)?;
+                self.end(w)
+            }
+            RESPType::Extension(ext) => {
+                self.start(w, "35")?;
+                w.write_fmt(format_args!("{} ", ext.tag as char))?;
+                write_quoted(w, &ext.payload)?;
+                self.end(w)
+            }
+            RESPType::Null => {
+                self.start(w, "90")?;
+                w.write_str("(null)")?;
+                self.end(w)
+            }
+            RESPType::Array(items) => self.write_items(w, '*', items, depth),
+            RESPType::Set(items) => self.write_items(w, '~', items, depth),
+            RESPType::Push(items) => self.write_items(w, '>', items, depth),
+            RESPType::Map(entries) => self.write_entries(w, '%', entries, depth),
+            RESPType::Attributed { attrs, value } => {
+                self.write_entries(w, '|', attrs, depth)?;
+                w.write_char('\n')?;
+                self.write_value(w, value, depth)
+            }
+        }
+    }
+
+    fn write_entries<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        marker: char,
+        entries: &[(RESPType, RESPType)],
+        depth: usize,
+    ) -> fmt::Result {
+        let shown = self.write_header(w, marker, entries.len())?;
+        for (key, value) in &entries[..shown] {
+            w.write_char('\n')?;
+            self.write_value(w, key, depth + 1)?;
+            w.write_char('\n')?;
+            self.write_value(w, value, depth + 2)?;
+        }
+        self.write_more(w, entries.len() - shown, depth)
+    }
+
+    fn write_items<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        marker: char,
+        items: &[RESPType],
+        depth: usize,
+    ) -> fmt::Result {
+        let shown = self.write_header(w, marker, items.len())?;
+        for item in &items[..shown] {
+            w.write_char('\n')?;
+            self.write_value(w, item, depth + 1)?;
+        }
+        self.write_more(w, items.len() - shown, depth)
+    }
+
+    /// Writes an aggregate header and returns how many elements to show.
+    fn write_header<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        marker: char,
+        len: usize,
+    ) -> Result<usize, fmt::Error> {
+        self.start(w, "1")?;
+        w.write_fmt(format_args!("{}{}", marker, len))?;
+        self.end(w)?;
+        Ok(self.max_elements.unwrap_or(len).min(len))
+    }
+
+    fn write_more<W: fmt::Write>(&self, w: &mut W, hidden: usize, depth: usize) -> fmt::Result {
+        if hidden > 0 {
+            w.write_char('\n')?;
+            for _ in 0..(depth + 1) * self.indent {
+                w.write_char(' ')?;
+            }
+            w.write_fmt(format_args!("... (+{} more)", hidden))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "cli")]
+    fn start<W: fmt::Write>(&self, w: &mut W, code: &str) -> fmt::Result {
+        if self.color {
+            w.write_fmt(format_args!("\x1b[{}m", code))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "cli"))]
+    fn start<W: fmt::Write>(&self, _w: &mut W, _code: &str) -> fmt::Result {
+        Ok(())
+    }
+
+    #[cfg(feature = "cli")]
+    fn end<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        if self.color {
+            w.write_str(RESET)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "cli"))]
+    fn end<W: fmt::Write>(&self, _w: &mut W) -> fmt::Result {
+        Ok(())
+    }
+}
+
+fn write_quoted<W: fmt::Write>(w: &mut W, bytes: &[u8]) -> fmt::Result {
+    w.write_char('"')?;
+    escape::write_escaped(w, bytes)?;
+    w.write_char('"')
+}
+
+/// A value paired with the printer used to display it.
+pub struct Pretty<'a> {
+    printer: &'a PrettyPrinter,
+    value: &'a RESPType,
+}
+
+impl<'a> fmt::Display for Pretty<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.printer.write(f, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RESPType {
+        RESPType::Array(vec![
+            RESPType::Integer(42),
+            RESPType::SimpleString("OK".to_string()),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"hello\r\n".to_vec()),
+                RESPType::Null,
+                RESPType::Error("ERR x".to_string()),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn prints_nested_tree() {
+        let have = PrettyPrinter::new().print(&sample());
+        let want = "*3\n  :42\n  +\"OK\"\n  *3\n    $7 \"hello\\r\\n\"\n    (null)\n    -\"ERR x\"";
+        assert_eq!(have, want);
+    }
+
+    #[test]
+    fn custom_indent() {
+        let have = PrettyPrinter::new().indent(4).print(&sample());
+        assert!(have.contains("\n        $7"));
+    }
+
+    #[test]
+    fn truncates_bulk_strings() {
+        let value = RESPType::BulkString(b"abcdefgh".to_vec());
+        let have = PrettyPrinter::new().max_bytes(3).print(&value);
+        assert_eq!(have, "$8 \"abc\" (+5 bytes)");
+    }
+
+    #[test]
+    fn truncates_arrays() {
+        let value = RESPType::Array((0..5).map(RESPType::Integer).collect());
+        let have = PrettyPrinter::new().max_elements(2).print(&value);
+        assert_eq!(have, "*5\n  :0\n  :1\n  ... (+3 more)");
+    }
+
+    #[test]
+    fn display_adapter() {
+        let printer = PrettyPrinter::new();
+        let value = RESPType::Integer(7);
+        assert_eq!(format!("reply: {}", printer.display(&value)), "reply: :7");
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn colors_values() {
+        let have = PrettyPrinter::new()
+            .color(true)
+            .print(&RESPType::Integer(1));
+        assert_eq!(have, "\x1b[36m:1\x1b[0m");
+    }
+
+    #[test]
+    fn prints_maps_and_sets() {
+        let value = RESPType::Map(vec![
+            (
+                RESPType::SimpleString("a".to_string()),
+                RESPType::Set(vec![RESPType::Integer(1), RESPType::Boolean(true)]),
+            ),
+            (
+                RESPType::SimpleString("b".to_string()),
+                RESPType::Double(0.5),
+            ),
+        ]);
+        let have = PrettyPrinter::new().print(&value);
+        assert_eq!(
+            have,
+            "%2\n  +\"a\"\n    ~2\n      :1\n      #t\n  +\"b\"\n    ,0.5"
+        );
+        let have = PrettyPrinter::new().max_elements(1).print(&value);
+        assert!(have.ends_with("\n  ... (+1 more)"));
+    }
+
+    #[test]
+    fn prints_attributes_before_value() {
+        let value = RESPType::Attributed {
+            attrs: vec![(
+                RESPType::SimpleString("ttl".to_string()),
+                RESPType::Integer(5),
+            )],
+            value: Box::new(RESPType::Integer(1)),
+        };
+        assert_eq!(
+            PrettyPrinter::new().print(&value),
+            "|1\n  +\"ttl\"\n    :5\n:1"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! ACL WHOAMI, ACL CAT, ACL LIST and ACL GETUSER replies.
+
+use super::{array, bytes, pairs, string, strings};
+use crate::{Error, RESPType, Result};
+
+/// Which operations a key pattern grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAccess {
+    ReadWrite,
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPattern {
+    pub access: KeyAccess,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandRule {
+    Allow(String),
+    Deny(String),
+    AllowCategory(String),
+    DenyCategory(String),
+}
+
+/// An ACL rule string such as `on nopass ~* &* +@all`, decomposed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AclRules {
+    /// Bare keywords such as `on`, `off`, `nopass` or `resetkeys`.
+    pub flags: Vec<String>,
+    /// Password rules (`>pass`, `<pass`, `#hash`, `!hash`) as written.
+    pub passwords: Vec<String>,
+    pub keys: Vec<KeyPattern>,
+    pub channels: Vec<String>,
+    pub commands: Vec<CommandRule>,
+    /// Parenthesized selectors, e.g. `(~temp:* +get)`.
+    pub selectors: Vec<AclRules>,
+}
+
+impl AclRules {
+    pub fn parse(rules: &str) -> Result<AclRules> {
+        let mut out = AclRules::default();
+        for token in tokenize(rules)? {
+            out.add(token)?;
+        }
+        Ok(out)
+    }
+
+    fn add(&mut self, token: &str) -> Result<()> {
+        if token.starts_with('(') && token.ends_with(')') {
+            self.selectors
+                .push(AclRules::parse(&token[1..token.len() - 1])?);
+            return Ok(());
+        }
+        match token.as_bytes()[0] {
+            b'>' | b'<' | b'#' | b'!' => self.passwords.push(token.to_string()),
+            b'~' => self.keys.push(KeyPattern {
+                access: KeyAccess::ReadWrite,
+                pattern: token[1..].to_string(),
+            }),
+            b'%' => self.keys.push(parse_key_permission(token)?),
+            b'&' => self.channels.push(token[1..].to_string()),
+            b'+' | b'-' => self.commands.push(parse_command_rule(token)),
+            _ => self.flags.push(token.to_string()),
+        }
+        Ok(())
+    }
+}
+
+fn tokenize(rules: &str) -> Result<Vec<&str>> {
+    let mut tokens = Vec::new();
+    let mut rest = rules.trim_start();
+    while !rest.is_empty() {
+        let end = if rest.starts_with('(') {
+            rest.find(')')
+                .map(|i| i + 1)
+                .ok_or_else(|| Error::InvalidReply(format!("unclosed selector in {:?}", rules)))?
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        tokens.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_key_permission(token: &str) -> Result<KeyPattern> {
+    let tilde = token
+        .find('~')
+        .ok_or_else(|| Error::InvalidReply(format!("bad key permission {:?}", token)))?;
+    let access = match &token[1..tilde] {
+        "R" => KeyAccess::Read,
+        "W" => KeyAccess::Write,
+        "RW" | "WR" => KeyAccess::ReadWrite,
+        _ => {
+            return Err(Error::InvalidReply(format!(
+                "bad key permission {:?}",
+                token
+            )))
+        }
+    };
+    Ok(KeyPattern {
+        access,
+        pattern: token[tilde + 1..].to_string(),
+    })
+}
+
+fn parse_command_rule(token: &str) -> CommandRule {
+    let allow = token.starts_with('+');
+    let body = &token[1..];
+    match (allow, body.strip_prefix('@')) {
+        (true, Some(cat)) => CommandRule::AllowCategory(cat.to_string()),
+        (false, Some(cat)) => CommandRule::DenyCategory(cat.to_string()),
+        (true, None) => CommandRule::Allow(body.to_string()),
+        (false, None) => CommandRule::Deny(body.to_string()),
+    }
+}
+
+/// One line of ACL LIST output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclUser {
+    pub name: String,
+    pub rules: AclRules,
+}
+
+/// Decodes the ACL WHOAMI reply.
+pub fn parse_whoami(value: &RESPType) -> Result<String> {
+    string(value)
+}
+
+/// Decodes the ACL CAT reply, either the category list or the commands of
+/// one category.
+pub fn parse_cat(value: &RESPType) -> Result<Vec<String>> {
+    strings(value)
+}
+
+/// Decodes the ACL LIST reply, whose lines look like
+/// `user default on nopass ~* &* +@all`.
+pub fn parse_list(value: &RESPType) -> Result<Vec<AclUser>> {
+    array(value)?
+        .iter()
+        .map(|line| {
+            let line = string(line)?;
+            let mut parts = line.splitn(3, ' ');
+            match (parts.next(), parts.next()) {
+                (Some("user"), Some(name)) => Ok(AclUser {
+                    name: name.to_string(),
+                    rules: AclRules::parse(parts.next().unwrap_or(""))?,
+                }),
+                _ => Err(Error::InvalidReply(format!("bad ACL LIST line {:?}", line))),
+            }
+        })
+        .collect()
+}
+
+/// Decodes the ACL GETUSER reply into the same shape as a rule string.
+///
+/// Redis 6 reports key and channel patterns as arrays while Redis 7 uses
+/// rule strings such as `~* &*`; both are accepted. Unknown fields are
+/// ignored. Returns `None` for a null reply (no such user).
+pub fn parse_getuser(value: &RESPType) -> Result<Option<AclRules>> {
+    if let RESPType::Null = value.without_attributes() {
+        return Ok(None);
+    }
+    getuser_fields(value).map(Some)
+}
+
+fn getuser_fields(value: &RESPType) -> Result<AclRules> {
+    let mut out = AclRules::default();
+    for (key, field) in pairs(value)? {
+        match bytes(key)? {
+            b"flags" => out.flags = strings(field)?,
+            b"passwords" => out.passwords = strings(field)?,
+            b"commands" => {
+                let rules = AclRules::parse(&string(field)?)?;
+                out.commands = rules.commands;
+            }
+            b"keys" => out.keys = patterns(field, '~')?.keys,
+            b"channels" => out.channels = patterns(field, '&')?.channels,
+            b"selectors" => {
+                out.selectors = array(field)?
+                    .iter()
+                    .map(getuser_fields)
+                    .collect::<Result<_>>()?
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Accepts a rule string, or an array of bare patterns that are prefixed
+/// with `prefix` before parsing.
+fn patterns(value: &RESPType, prefix: char) -> Result<AclRules> {
+    match value.without_attributes() {
+        RESPType::Array(_) | RESPType::Set(_) => {
+            let mut out = AclRules::default();
+            for pattern in strings(value)? {
+                out.add(&format!("{}{}", prefix, pattern))?;
+            }
+            Ok(out)
+        }
+        _ => AclRules::parse(&string(value)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+
+    fn bulks(items: &[&str]) -> RESPType {
+        RESPType::Array(items.iter().map(|s| bulk(s)).collect())
+    }
+
+    #[test]
+    fn decomposes_rule_strings() {
+        let rules =
+            AclRules::parse("on #abc ~app:* %R~ro:* &news.* +@read -debug (~tmp:* +get)").unwrap();
+        assert_eq!(rules.flags, vec!["on".to_string()]);
+        assert_eq!(rules.passwords, vec!["#abc".to_string()]);
+        assert_eq!(
+            rules.keys,
+            vec![
+                KeyPattern {
+                    access: KeyAccess::ReadWrite,
+                    pattern: "app:*".to_string()
+                },
+                KeyPattern {
+                    access: KeyAccess::Read,
+                    pattern: "ro:*".to_string()
+                },
+            ]
+        );
+        assert_eq!(rules.channels, vec!["news.*".to_string()]);
+        assert_eq!(
+            rules.commands,
+            vec![
+                CommandRule::AllowCategory("read".to_string()),
+                CommandRule::Deny("debug".to_string()),
+            ]
+        );
+        assert_eq!(rules.selectors.len(), 1);
+        assert_eq!(
+            rules.selectors[0].commands,
+            vec![CommandRule::Allow("get".to_string())]
+        );
+    }
+
+    #[test]
+    fn parses_list() {
+        let reply = bulks(&["user default on nopass ~* &* +@all", "user bob off"]);
+        let users = parse_list(&reply).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].name, "default");
+        assert_eq!(users[0].rules.flags, vec!["on", "nopass"]);
+        assert_eq!(users[1].rules.flags, vec!["off"]);
+        assert!(parse_list(&bulks(&["nonsense"])).is_err());
+    }
+
+    #[test]
+    fn parses_getuser_redis7() {
+        let reply = RESPType::Array(vec![
+            bulk("flags"),
+            bulks(&["on", "sanitize-payload"]),
+            bulk("passwords"),
+            bulks(&[]),
+            bulk("commands"),
+            bulk("+@all -flushdb"),
+            bulk("keys"),
+            bulk("~* %W~logs:*"),
+            bulk("channels"),
+            bulk("&*"),
+            bulk("selectors"),
+            RESPType::Array(vec![RESPType::Array(vec![
+                bulk("commands"),
+                bulk("+get"),
+                bulk("keys"),
+                bulk("~cache:*"),
+                bulk("channels"),
+                bulk(""),
+            ])]),
+        ]);
+        let user = parse_getuser(&reply).unwrap().unwrap();
+        assert_eq!(user.flags, vec!["on", "sanitize-payload"]);
+        assert_eq!(user.commands.len(), 2);
+        assert_eq!(user.keys[1].access, KeyAccess::Write);
+        assert_eq!(user.channels, vec!["*"]);
+        assert_eq!(user.selectors[0].keys[0].pattern, "cache:*");
+    }
+
+    #[test]
+    fn parses_getuser_redis6() {
+        let reply = RESPType::Array(vec![
+            bulk("flags"),
+            bulks(&["on", "allkeys"]),
+            bulk("keys"),
+            bulks(&["a:*", "b:*"]),
+            bulk("channels"),
+            bulks(&["*"]),
+            bulk("commands"),
+            bulk("+@all"),
+        ]);
+        let user = parse_getuser(&reply).unwrap().unwrap();
+        assert_eq!(user.keys[1].pattern, "b:*");
+        assert_eq!(user.channels, vec!["*"]);
+        assert_eq!(parse_getuser(&RESPType::Null).unwrap(), None);
+    }
+
+    #[test]
+    fn parses_getuser_through_attributes_and_sets() {
+        let reply = RESPType::Map(vec![(
+            bulk("keys"),
+            RESPType::Set(vec![bulk("a:*"), bulk("b:*")]),
+        )]);
+        let user = parse_getuser(&reply).unwrap().unwrap();
+        assert_eq!(user.keys[1].pattern, "b:*");
+        let null = RESPType::Attributed {
+            attrs: vec![(bulk("ttl"), RESPType::Integer(1))],
+            value: Box::new(RESPType::Null),
+        };
+        assert_eq!(parse_getuser(&null).unwrap(), None);
+    }
+
+    #[test]
+    fn parses_whoami_and_cat() {
+        assert_eq!(parse_whoami(&bulk("default")).unwrap(), "default");
+        assert_eq!(
+            parse_cat(&bulks(&["keyspace", "read"])).unwrap(),
+            vec!["keyspace", "read"]
+        );
+        assert!(parse_whoami(&RESPType::Integer(1)).is_err());
+    }
+}
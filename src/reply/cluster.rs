@@ -0,0 +1,189 @@
+//! The CLUSTER NODES reply.
+
+use super::string;
+use crate::{Error, RESPType, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    Connected,
+    Disconnected,
+}
+
+/// An inclusive range of hash slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+/// A slot being moved between nodes, with the id of the other node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotTransfer {
+    pub slot: u16,
+    pub node_id: String,
+}
+
+/// One line of CLUSTER NODES output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterNode {
+    pub id: String,
+    /// `ip:port@cport`, optionally followed by `,hostname`.
+    pub addr: String,
+    /// Flags such as `myself`, `master`, `slave` or `fail?`.
+    pub flags: Vec<String>,
+    /// The primary of a replica; `None` for primaries.
+    pub master_id: Option<String>,
+    pub ping_sent: u64,
+    pub pong_recv: u64,
+    pub config_epoch: u64,
+    pub link_state: LinkState,
+    pub slots: Vec<SlotRange>,
+    /// Slots this node is migrating to another node (`[slot->-id]`).
+    pub migrating: Vec<SlotTransfer>,
+    /// Slots this node is importing from another node (`[slot-<-id]`).
+    pub importing: Vec<SlotTransfer>,
+}
+
+impl ClusterNode {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+/// Decodes the CLUSTER NODES reply, a bulk string with one node per line.
+pub fn parse_nodes(value: &RESPType) -> Result<Vec<ClusterNode>> {
+    string(value)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_line)
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<ClusterNode> {
+    let bad = || Error::InvalidReply(format!("bad CLUSTER NODES line {:?}", line));
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 8 {
+        return Err(bad());
+    }
+    let number = |s: &str| s.parse::<u64>().map_err(|_| bad());
+    let link_state = match fields[7] {
+        "connected" => LinkState::Connected,
+        "disconnected" => LinkState::Disconnected,
+        _ => return Err(bad()),
+    };
+    let mut node = ClusterNode {
+        id: fields[0].to_string(),
+        addr: fields[1].to_string(),
+        flags: fields[2].split(',').map(str::to_string).collect(),
+        master_id: match fields[3] {
+            "-" => None,
+            id => Some(id.to_string()),
+        },
+        ping_sent: number(fields[4])?,
+        pong_recv: number(fields[5])?,
+        config_epoch: number(fields[6])?,
+        link_state,
+        slots: Vec::new(),
+        migrating: Vec::new(),
+        importing: Vec::new(),
+    };
+    for slot in &fields[8..] {
+        add_slot(&mut node, slot).ok_or_else(bad)?;
+    }
+    Ok(node)
+}
+
+fn add_slot(node: &mut ClusterNode, slot: &str) -> Option<()> {
+    if let Some(inner) = slot.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        if let Some((n, id)) = inner.split_once("->-") {
+            node.migrating.push(SlotTransfer {
+                slot: n.parse().ok()?,
+                node_id: id.to_string(),
+            });
+        } else {
+            let (n, id) = inner.split_once("-<-")?;
+            node.importing.push(SlotTransfer {
+                slot: n.parse().ok()?,
+                node_id: id.to_string(),
+            });
+        }
+        return Some(());
+    }
+    let (start, end) = match slot.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let n = slot.parse().ok()?;
+            (n, n)
+        }
+    };
+    node.slots.push(SlotRange { start, end });
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODES: &str = "\
+07c37dfeb235213a872192d90877d0cd55635b91 127.0.0.1:30004@31004,host-4 slave e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 0 1426238317239 4 connected
+67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 127.0.0.1:30002@31002 master - 0 1426238316232 2 connected 5461-10922 [5461-<-e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca]
+e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001 myself,master - 0 0 1 connected 0-5460 16000 [5461->-67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1]
+6ec23923021cf3ffec47632106199cb7f496ce01 127.0.0.1:30005@31005 slave,fail? 67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 0 1426238316232 5 disconnected
+";
+
+    #[test]
+    fn parses_nodes() {
+        let nodes = parse_nodes(&RESPType::BulkString(NODES.as_bytes().to_vec())).unwrap();
+        assert_eq!(nodes.len(), 4);
+
+        assert_eq!(nodes[0].addr, "127.0.0.1:30004@31004,host-4");
+        assert_eq!(
+            nodes[0].master_id.as_deref(),
+            Some("e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca")
+        );
+        assert!(nodes[0].slots.is_empty());
+
+        assert_eq!(
+            nodes[1].slots,
+            vec![SlotRange {
+                start: 5461,
+                end: 10922
+            }]
+        );
+        assert_eq!(nodes[1].importing[0].slot, 5461);
+
+        assert!(nodes[2].has_flag("myself"));
+        assert_eq!(nodes[2].master_id, None);
+        assert_eq!(
+            nodes[2].slots,
+            vec![
+                SlotRange {
+                    start: 0,
+                    end: 5460
+                },
+                SlotRange {
+                    start: 16000,
+                    end: 16000
+                },
+            ]
+        );
+        assert_eq!(
+            nodes[2].migrating,
+            vec![SlotTransfer {
+                slot: 5461,
+                node_id: "67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1".to_string(),
+            }]
+        );
+
+        assert!(nodes[3].has_flag("fail?"));
+        assert_eq!(nodes[3].link_state, LinkState::Disconnected);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let reply =
+            RESPType::BulkString(b"abc 127.0.0.1:1@2 master - 0 0 1 connected x-y".to_vec());
+        assert!(parse_nodes(&reply).is_err());
+        assert!(parse_nodes(&RESPType::BulkString(b"abc master".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Classification of error replies by their leading code word.
+
+use crate::RESPType;
+use std::fmt;
+
+/// The code that starts an error reply, e.g. `WRONGTYPE` in
+/// `-WRONGTYPE Operation against a key holding the wrong kind of value`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KnownErrorCode {
+    Ask,
+    Busy,
+    BusyGroup,
+    BusyKey,
+    ClusterDown,
+    CrossSlot,
+    Err,
+    ExecAbort,
+    Loading,
+    MasterDown,
+    Misconf,
+    Moved,
+    NoAuth,
+    NoPerm,
+    NoProto,
+    NoReplicas,
+    NoScript,
+    Oom,
+    ReadOnly,
+    TryAgain,
+    WrongPass,
+    WrongType,
+    /// A code not listed above, as sent by the server.
+    Other(String),
+}
+
+const CODES: &[(&str, KnownErrorCode)] = &[
+    ("ASK", KnownErrorCode::Ask),
+    ("BUSY", KnownErrorCode::Busy),
+    ("BUSYGROUP", KnownErrorCode::BusyGroup),
+    ("BUSYKEY", KnownErrorCode::BusyKey),
+    ("CLUSTERDOWN", KnownErrorCode::ClusterDown),
+    ("CROSSSLOT", KnownErrorCode::CrossSlot),
+    ("ERR", KnownErrorCode::Err),
+    ("EXECABORT", KnownErrorCode::ExecAbort),
+    ("LOADING", KnownErrorCode::Loading),
+    ("MASTERDOWN", KnownErrorCode::MasterDown),
+    ("MISCONF", KnownErrorCode::Misconf),
+    ("MOVED", KnownErrorCode::Moved),
+    ("NOAUTH", KnownErrorCode::NoAuth),
+    ("NOPERM", KnownErrorCode::NoPerm),
+    ("NOPROTO", KnownErrorCode::NoProto),
+    ("NOREPLICAS", KnownErrorCode::NoReplicas),
+    ("NOSCRIPT", KnownErrorCode::NoScript),
+    ("OOM", KnownErrorCode::Oom),
+    ("READONLY", KnownErrorCode::ReadOnly),
+    ("TRYAGAIN", KnownErrorCode::TryAgain),
+    ("WRONGPASS", KnownErrorCode::WrongPass),
+    ("WRONGTYPE", KnownErrorCode::WrongType),
+];
+
+impl KnownErrorCode {
+    /// Classifies an error message by its first word. Codes are matched
+    /// case-sensitively, as servers always send them in upper case.
+    pub fn parse(message: &str) -> KnownErrorCode {
+        let word = message.split(' ').next().unwrap_or("");
+        CODES
+            .iter()
+            .find(|(name, _)| *name == word)
+            .map(|(_, code)| code.clone())
+            .unwrap_or_else(|| KnownErrorCode::Other(word.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            KnownErrorCode::Other(word) => word,
+            known => CODES
+                .iter()
+                .find(|(_, code)| code == known)
+                .map(|(name, _)| *name)
+                .unwrap_or(""),
+        }
+    }
+}
+
+impl fmt::Display for KnownErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl RESPType {
+    /// The code of an error reply, or `None` if this is not an error.
+    pub fn error_code(&self) -> Option<KnownErrorCode> {
+        match self {
+            RESPType::Error(message) => Some(KnownErrorCode::parse(message)),
+            RESPType::BulkError(message) => {
+                Some(KnownErrorCode::parse(&String::from_utf8_lossy(message)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_error_replies() {
+        let reply = RESPType::Error(
+            "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+        );
+        assert_eq!(reply.error_code(), Some(KnownErrorCode::WrongType));
+        assert_eq!(
+            KnownErrorCode::parse("MOVED 3999 127.0.0.1:6381"),
+            KnownErrorCode::Moved
+        );
+        assert_eq!(KnownErrorCode::parse("ERR"), KnownErrorCode::Err);
+        assert_eq!(RESPType::Integer(1).error_code(), None);
+        let bulk = RESPType::BulkError(b"NOSCRIPT No matching script\n".to_vec());
+        assert_eq!(bulk.error_code(), Some(KnownErrorCode::NoScript));
+    }
+
+    #[test]
+    fn keeps_unknown_codes() {
+        let code = KnownErrorCode::parse("FOOBAR something went wrong");
+        assert_eq!(code, KnownErrorCode::Other("FOOBAR".to_string()));
+        assert_eq!(code.to_string(), "FOOBAR");
+        assert_eq!(KnownErrorCode::NoScript.to_string(), "NOSCRIPT");
+    }
+}
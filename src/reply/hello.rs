@@ -0,0 +1,134 @@
+//! The HELLO reply.
+
+use super::{array, bytes, integer, pairs, string};
+use crate::{Error, RESPType, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub version: i64,
+}
+
+/// The connection properties a server reports in reply to HELLO. Fields
+/// this type does not know about are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerHello {
+    pub server: String,
+    pub version: String,
+    pub proto: i64,
+    pub id: i64,
+    pub mode: String,
+    pub role: String,
+    pub modules: Vec<ModuleInfo>,
+}
+
+/// Decodes the HELLO reply. `server`, `version` and `proto` are required;
+/// other fields default when absent.
+pub fn parse_hello(value: &RESPType) -> Result<ServerHello> {
+    let mut hello = ServerHello::default();
+    let mut seen = (false, false, false);
+    for (key, field) in pairs(value)? {
+        match bytes(key)? {
+            b"server" => {
+                hello.server = string(field)?;
+                seen.0 = true;
+            }
+            b"version" => {
+                hello.version = string(field)?;
+                seen.1 = true;
+            }
+            b"proto" => {
+                hello.proto = integer(field)?;
+                seen.2 = true;
+            }
+            b"id" => hello.id = integer(field)?,
+            b"mode" => hello.mode = string(field)?,
+            b"role" => hello.role = string(field)?,
+            b"modules" => {
+                hello.modules = array(field)?
+                    .iter()
+                    .map(parse_module)
+                    .collect::<Result<_>>()?
+            }
+            _ => {}
+        }
+    }
+    match seen {
+        (true, true, true) => Ok(hello),
+        _ => Err(Error::InvalidReply(
+            "HELLO reply lacks server, version or proto".to_string(),
+        )),
+    }
+}
+
+fn parse_module(value: &RESPType) -> Result<ModuleInfo> {
+    let mut module = ModuleInfo {
+        name: String::new(),
+        version: 0,
+    };
+    for (key, field) in pairs(value)? {
+        match bytes(key)? {
+            b"name" => module.name = string(field)?,
+            b"ver" => module.version = integer(field)?,
+            _ => {}
+        }
+    }
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+
+    #[test]
+    fn parses_hello() {
+        let reply = RESPType::Array(vec![
+            bulk("server"),
+            bulk("redis"),
+            bulk("version"),
+            bulk("7.2.4"),
+            bulk("proto"),
+            RESPType::Integer(3),
+            bulk("id"),
+            RESPType::Integer(42),
+            bulk("mode"),
+            bulk("standalone"),
+            bulk("role"),
+            bulk("master"),
+            bulk("availability_zone"),
+            bulk("eu-1a"),
+            bulk("modules"),
+            RESPType::Array(vec![RESPType::Array(vec![
+                bulk("name"),
+                bulk("search"),
+                bulk("ver"),
+                RESPType::Integer(20809),
+                bulk("path"),
+                bulk("/usr/lib/redis/modules/redisearch.so"),
+            ])]),
+        ]);
+        assert_eq!(
+            parse_hello(&reply).unwrap(),
+            ServerHello {
+                server: "redis".to_string(),
+                version: "7.2.4".to_string(),
+                proto: 3,
+                id: 42,
+                mode: "standalone".to_string(),
+                role: "master".to_string(),
+                modules: vec![ModuleInfo {
+                    name: "search".to_string(),
+                    version: 20809,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn requires_core_fields() {
+        let reply = RESPType::Array(vec![bulk("server"), bulk("redis")]);
+        assert!(parse_hello(&reply).is_err());
+        assert!(parse_hello(&RESPType::Error("NOPROTO".to_string())).is_err());
+    }
+}
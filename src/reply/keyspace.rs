@@ -0,0 +1,216 @@
+//! Keyspace notifications.
+
+use std::fmt;
+
+/// The event named by a `__keyevent@<db>__:<event>` channel, or carried as
+/// the message of a `__keyspace@<db>__:<key>` notification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeyEvent {
+    Append,
+    CopyTo,
+    Del,
+    Evicted,
+    Expire,
+    Expired,
+    HDel,
+    HExpired,
+    HIncrBy,
+    HIncrByFloat,
+    HSet,
+    IncrBy,
+    IncrByFloat,
+    LInsert,
+    LPop,
+    LPush,
+    LRem,
+    LSet,
+    LTrim,
+    MoveFrom,
+    MoveTo,
+    New,
+    Persist,
+    RenameFrom,
+    RenameTo,
+    Restore,
+    RPop,
+    RPush,
+    SAdd,
+    SDiffStore,
+    Set,
+    SetRange,
+    SInterStore,
+    SPop,
+    SRem,
+    SUnionStore,
+    XAdd,
+    XDel,
+    XGroupCreate,
+    XGroupCreateConsumer,
+    XGroupDelConsumer,
+    XGroupDestroy,
+    XGroupSetId,
+    XSetId,
+    XTrim,
+    ZAdd,
+    ZDiffStore,
+    ZIncr,
+    ZInterStore,
+    ZRem,
+    ZRemRangeByLex,
+    ZRemRangeByRank,
+    ZRemRangeByScore,
+    ZUnionStore,
+    /// An event not listed above, as sent by the server.
+    Unknown(String),
+}
+
+const EVENTS: &[(&str, KeyEvent)] = &[
+    ("append", KeyEvent::Append),
+    ("copy_to", KeyEvent::CopyTo),
+    ("del", KeyEvent::Del),
+    ("evicted", KeyEvent::Evicted),
+    ("expire", KeyEvent::Expire),
+    ("expired", KeyEvent::Expired),
+    ("hdel", KeyEvent::HDel),
+    ("hexpired", KeyEvent::HExpired),
+    ("hincrby", KeyEvent::HIncrBy),
+    ("hincrbyfloat", KeyEvent::HIncrByFloat),
+    ("hset", KeyEvent::HSet),
+    ("incrby", KeyEvent::IncrBy),
+    ("incrbyfloat", KeyEvent::IncrByFloat),
+    ("linsert", KeyEvent::LInsert),
+    ("lpop", KeyEvent::LPop),
+    ("lpush", KeyEvent::LPush),
+    ("lrem", KeyEvent::LRem),
+    ("lset", KeyEvent::LSet),
+    ("ltrim", KeyEvent::LTrim),
+    ("move_from", KeyEvent::MoveFrom),
+    ("move_to", KeyEvent::MoveTo),
+    ("new", KeyEvent::New),
+    ("persist", KeyEvent::Persist),
+    ("rename_from", KeyEvent::RenameFrom),
+    ("rename_to", KeyEvent::RenameTo),
+    ("restore", KeyEvent::Restore),
+    ("rpop", KeyEvent::RPop),
+    ("rpush", KeyEvent::RPush),
+    ("sadd", KeyEvent::SAdd),
+    ("sdiffstore", KeyEvent::SDiffStore),
+    ("set", KeyEvent::Set),
+    ("setrange", KeyEvent::SetRange),
+    ("sinterstore", KeyEvent::SInterStore),
+    ("spop", KeyEvent::SPop),
+    ("srem", KeyEvent::SRem),
+    ("sunionstore", KeyEvent::SUnionStore),
+    ("xadd", KeyEvent::XAdd),
+    ("xdel", KeyEvent::XDel),
+    ("xgroup-create", KeyEvent::XGroupCreate),
+    ("xgroup-createconsumer", KeyEvent::XGroupCreateConsumer),
+    ("xgroup-delconsumer", KeyEvent::XGroupDelConsumer),
+    ("xgroup-destroy", KeyEvent::XGroupDestroy),
+    ("xgroup-setid", KeyEvent::XGroupSetId),
+    ("xsetid", KeyEvent::XSetId),
+    ("xtrim", KeyEvent::XTrim),
+    ("zadd", KeyEvent::ZAdd),
+    ("zdiffstore", KeyEvent::ZDiffStore),
+    ("zincr", KeyEvent::ZIncr),
+    ("zinterstore", KeyEvent::ZInterStore),
+    ("zrem", KeyEvent::ZRem),
+    ("zremrangebylex", KeyEvent::ZRemRangeByLex),
+    ("zremrangebyrank", KeyEvent::ZRemRangeByRank),
+    ("zremrangebyscore", KeyEvent::ZRemRangeByScore),
+    ("zunionstore", KeyEvent::ZUnionStore),
+];
+
+impl KeyEvent {
+    pub fn parse(name: &str) -> KeyEvent {
+        EVENTS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, event)| event.clone())
+            .unwrap_or_else(|| KeyEvent::Unknown(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            KeyEvent::Unknown(name) => name,
+            known => EVENTS
+                .iter()
+                .find(|(_, event)| event == known)
+                .map(|(name, _)| *name)
+                .unwrap_or(""),
+        }
+    }
+}
+
+impl fmt::Display for KeyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A keyspace notification channel name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationChannel {
+    /// `__keyspace@<db>__:<key>`; the message names the event.
+    Keyspace { db: u32, key: Vec<u8> },
+    /// `__keyevent@<db>__:<event>`; the message names the key.
+    Keyevent { db: u32, event: KeyEvent },
+}
+
+/// Decodes a channel name, returning `None` for channels that are not
+/// keyspace notifications.
+pub fn parse_channel(channel: &[u8]) -> Option<NotificationChannel> {
+    let (space, rest) = if let Some(rest) = channel.strip_prefix(b"__keyspace@") {
+        (true, rest)
+    } else {
+        (false, channel.strip_prefix(b"__keyevent@")?)
+    };
+    let sep = rest.windows(3).position(|w| w == b"__:")?;
+    let db = std::str::from_utf8(&rest[..sep]).ok()?.parse().ok()?;
+    let tail = &rest[sep + 3..];
+    if space {
+        Some(NotificationChannel::Keyspace {
+            db,
+            key: tail.to_vec(),
+        })
+    } else {
+        let event = KeyEvent::parse(std::str::from_utf8(tail).ok()?);
+        Some(NotificationChannel::Keyevent { db, event })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_event_names() {
+        assert_eq!(KeyEvent::parse("rename_from"), KeyEvent::RenameFrom);
+        assert_eq!(KeyEvent::parse("xgroup-create"), KeyEvent::XGroupCreate);
+        assert_eq!(
+            KeyEvent::parse("custom"),
+            KeyEvent::Unknown("custom".to_string())
+        );
+        assert_eq!(KeyEvent::Expired.to_string(), "expired");
+    }
+
+    #[test]
+    fn parses_channels() {
+        assert_eq!(
+            parse_channel(b"__keyevent@0__:expired"),
+            Some(NotificationChannel::Keyevent {
+                db: 0,
+                event: KeyEvent::Expired
+            })
+        );
+        assert_eq!(
+            parse_channel(b"__keyspace@12__:user:__:1"),
+            Some(NotificationChannel::Keyspace {
+                db: 12,
+                key: b"user:__:1".to_vec()
+            })
+        );
+        assert_eq!(parse_channel(b"news"), None);
+        assert_eq!(parse_channel(b"__keyspace@x__:k"), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! LATENCY LATEST and LATENCY HISTORY replies.
+
+use super::{array, integer, string};
+use crate::{Error, RESPType, Result};
+
+/// A latency sample for one monitored event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyEvent {
+    pub event: String,
+    /// Unix time of the sample, in seconds.
+    pub timestamp: i64,
+    pub latency_ms: i64,
+    /// All-time maximum for the event. Only LATENCY LATEST reports it.
+    pub max_ms: Option<i64>,
+}
+
+/// Decodes the LATENCY LATEST reply: one `[event, timestamp, latest, max]`
+/// entry per event.
+pub fn parse_latest(value: &RESPType) -> Result<Vec<LatencyEvent>> {
+    array(value)?
+        .iter()
+        .map(|entry| match array(entry)? {
+            [event, timestamp, latest, max] => Ok(LatencyEvent {
+                event: string(event)?,
+                timestamp: integer(timestamp)?,
+                latency_ms: integer(latest)?,
+                max_ms: Some(integer(max)?),
+            }),
+            other => Err(bad_entry(other.len())),
+        })
+        .collect()
+}
+
+/// Decodes the LATENCY HISTORY reply for `event`: `[timestamp, latency]`
+/// pairs, oldest first.
+pub fn parse_history(event: &str, value: &RESPType) -> Result<Vec<LatencyEvent>> {
+    array(value)?
+        .iter()
+        .map(|entry| match array(entry)? {
+            [timestamp, latency] => Ok(LatencyEvent {
+                event: event.to_string(),
+                timestamp: integer(timestamp)?,
+                latency_ms: integer(latency)?,
+                max_ms: None,
+            }),
+            other => Err(bad_entry(other.len())),
+        })
+        .collect()
+}
+
+fn bad_entry(len: usize) -> Error {
+    Error::InvalidReply(format!("unexpected latency entry with {} elements", len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(items: &[i64]) -> Vec<RESPType> {
+        items.iter().map(|&i| RESPType::Integer(i)).collect()
+    }
+
+    #[test]
+    fn parses_latest() {
+        let mut entry = vec![RESPType::BulkString(b"command".to_vec())];
+        entry.extend(ints(&[1_700_000_000, 250, 1000]));
+        let reply = RESPType::Array(vec![RESPType::Array(entry)]);
+        assert_eq!(
+            parse_latest(&reply).unwrap(),
+            vec![LatencyEvent {
+                event: "command".to_string(),
+                timestamp: 1_700_000_000,
+                latency_ms: 250,
+                max_ms: Some(1000),
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_history() {
+        let reply = RESPType::Array(vec![
+            RESPType::Array(ints(&[100, 5])),
+            RESPType::Array(ints(&[200, 7])),
+        ]);
+        let events = parse_history("fork", &reply).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].event, "fork");
+        assert_eq!(events[1].timestamp, 200);
+        assert_eq!(events[1].latency_ms, 7);
+        assert_eq!(events[1].max_ms, None);
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        let reply = RESPType::Array(vec![RESPType::Array(ints(&[100]))]);
+        assert!(parse_history("fork", &reply).is_err());
+        assert!(parse_latest(&reply).is_err());
+        assert_eq!(parse_latest(&RESPType::Array(vec![])).unwrap(), vec![]);
+    }
+}
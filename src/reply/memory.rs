@@ -0,0 +1,158 @@
+//! MEMORY STATS and MEMORY USAGE replies.
+
+use super::{float, integer, pairs, string};
+use crate::{RESPType, Result};
+use std::collections::HashMap;
+
+/// Overhead of one database's main and expires dictionaries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbOverhead {
+    pub main: i64,
+    pub expires: i64,
+}
+
+/// The MEMORY STATS reply. Fields missing from the reply (they vary between
+/// server versions) are `None`; fields this type does not know about are
+/// kept in `other`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStats {
+    pub peak_allocated: Option<i64>,
+    pub total_allocated: Option<i64>,
+    pub startup_allocated: Option<i64>,
+    pub replication_backlog: Option<i64>,
+    pub clients_slaves: Option<i64>,
+    pub clients_normal: Option<i64>,
+    pub aof_buffer: Option<i64>,
+    pub lua_caches: Option<i64>,
+    pub overhead_total: Option<i64>,
+    pub keys_count: Option<i64>,
+    pub keys_bytes_per_key: Option<i64>,
+    pub dataset_bytes: Option<i64>,
+    pub dataset_percentage: Option<f64>,
+    pub peak_percentage: Option<f64>,
+    pub fragmentation: Option<f64>,
+    pub fragmentation_bytes: Option<i64>,
+    /// Per-database overhead keyed by database index.
+    pub dbs: HashMap<u32, DbOverhead>,
+    pub other: HashMap<String, RESPType>,
+}
+
+/// Decodes the MEMORY STATS reply.
+pub fn parse_stats(value: &RESPType) -> Result<MemoryStats> {
+    let mut stats = MemoryStats::default();
+    for (key, field) in pairs(value)? {
+        let key = string(key)?;
+        if let Some(db) = key.strip_prefix("db.").and_then(|n| n.parse::<u32>().ok()) {
+            stats.dbs.insert(db, parse_db(field)?);
+            continue;
+        }
+        match key.as_str() {
+            "peak.allocated" => stats.peak_allocated = Some(integer(field)?),
+            "total.allocated" => stats.total_allocated = Some(integer(field)?),
+            "startup.allocated" => stats.startup_allocated = Some(integer(field)?),
+            "replication.backlog" => stats.replication_backlog = Some(integer(field)?),
+            "clients.slaves" => stats.clients_slaves = Some(integer(field)?),
+            "clients.normal" => stats.clients_normal = Some(integer(field)?),
+            "aof.buffer" => stats.aof_buffer = Some(integer(field)?),
+            "lua.caches" => stats.lua_caches = Some(integer(field)?),
+            "overhead.total" => stats.overhead_total = Some(integer(field)?),
+            "keys.count" => stats.keys_count = Some(integer(field)?),
+            "keys.bytes-per-key" => stats.keys_bytes_per_key = Some(integer(field)?),
+            "dataset.bytes" => stats.dataset_bytes = Some(integer(field)?),
+            "dataset.percentage" => stats.dataset_percentage = Some(float(field)?),
+            "peak.percentage" => stats.peak_percentage = Some(float(field)?),
+            "fragmentation" => stats.fragmentation = Some(float(field)?),
+            "fragmentation.bytes" => stats.fragmentation_bytes = Some(integer(field)?),
+            _ => {
+                stats.other.insert(key, field.clone());
+            }
+        }
+    }
+    Ok(stats)
+}
+
+fn parse_db(value: &RESPType) -> Result<DbOverhead> {
+    let mut db = DbOverhead::default();
+    for (key, field) in pairs(value)? {
+        match string(key)?.as_str() {
+            "overhead.hashtable.main" => db.main = integer(field)?,
+            "overhead.hashtable.expires" => db.expires = integer(field)?,
+            _ => {}
+        }
+    }
+    Ok(db)
+}
+
+/// Decodes the MEMORY USAGE reply: the number of bytes used by a key, or
+/// `None` if the key does not exist.
+pub fn parse_usage(value: &RESPType) -> Result<Option<i64>> {
+    match value.without_attributes() {
+        RESPType::Null => Ok(None),
+        other => integer(other).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+
+    #[test]
+    fn parses_stats() {
+        let reply = RESPType::Array(vec![
+            bulk("peak.allocated"),
+            RESPType::Integer(1000),
+            bulk("keys.count"),
+            RESPType::Integer(3),
+            bulk("db.0"),
+            RESPType::Array(vec![
+                bulk("overhead.hashtable.main"),
+                RESPType::Integer(72),
+                bulk("overhead.hashtable.expires"),
+                RESPType::Integer(0),
+            ]),
+            bulk("dataset.percentage"),
+            bulk("12.5"),
+            bulk("fragmentation"),
+            bulk("1.75"),
+            bulk("functions.caches"),
+            RESPType::Integer(184),
+        ]);
+        let stats = parse_stats(&reply).unwrap();
+        assert_eq!(stats.peak_allocated, Some(1000));
+        assert_eq!(stats.keys_count, Some(3));
+        assert_eq!(stats.total_allocated, None);
+        assert_eq!(stats.dataset_percentage, Some(12.5));
+        assert_eq!(stats.fragmentation, Some(1.75));
+        assert_eq!(
+            stats.dbs.get(&0),
+            Some(&DbOverhead {
+                main: 72,
+                expires: 0
+            })
+        );
+        assert_eq!(
+            stats.other.get("functions.caches"),
+            Some(&RESPType::Integer(184))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_field_types() {
+        let reply = RESPType::Array(vec![bulk("peak.allocated"), bulk("lots")]);
+        assert!(parse_stats(&reply).is_err());
+        assert!(parse_stats(&RESPType::Array(vec![bulk("odd")])).is_err());
+    }
+
+    #[test]
+    fn parses_usage() {
+        assert_eq!(parse_usage(&RESPType::Integer(56)).unwrap(), Some(56));
+        assert_eq!(parse_usage(&RESPType::Null).unwrap(), None);
+        let null = RESPType::Attributed {
+            attrs: Vec::new(),
+            value: Box::new(RESPType::Null),
+        };
+        assert_eq!(parse_usage(&null).unwrap(), None);
+        assert!(parse_usage(&bulk("56")).is_err());
+    }
+}
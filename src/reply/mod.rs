@@ -0,0 +1,152 @@
+//! Typed decoding of the replies to specific Redis commands.
+
+use crate::{Error, RESPType, Result};
+use error_code::KnownErrorCode;
+
+pub mod acl;
+pub mod cluster;
+pub mod error_code;
+pub mod hello;
+pub mod keyspace;
+pub mod latency;
+pub mod memory;
+pub mod object;
+pub mod pubsub;
+
+/// Reports `value` as not being of the `expected` type. A `WRONGTYPE`
+/// error reply is reported as such, since it means the command was run
+/// against the wrong kind of key rather than that the reply is malformed.
+pub(crate) fn invalid(expected: &'static str, value: &RESPType) -> Error {
+    match value {
+        RESPType::Error(msg) if value.error_code() == Some(KnownErrorCode::WrongType) => {
+            Error::WrongType(msg.clone())
+        }
+        RESPType::BulkError(msg) if value.error_code() == Some(KnownErrorCode::WrongType) => {
+            Error::WrongType(String::from_utf8_lossy(msg).into_owned())
+        }
+        _ => Error::TypeMismatch {
+            expected,
+            actual: value.kind(),
+        },
+    }
+}
+
+pub(crate) fn bytes(value: &RESPType) -> Result<&[u8]> {
+    match value.without_attributes() {
+        RESPType::SimpleString(s) => Ok(s.as_bytes()),
+        RESPType::BulkString(b) => Ok(b),
+        RESPType::Verbatim { text, .. } => Ok(text),
+        other => Err(invalid("string", other)),
+    }
+}
+
+pub(crate) fn string(value: &RESPType) -> Result<String> {
+    String::from_utf8(bytes(value)?.to_vec())
+        .map_err(|err| Error::InvalidReply(format!("invalid UTF-8: {}", err)))
+}
+
+pub(crate) fn integer(value: &RESPType) -> Result<i64> {
+    match value.without_attributes() {
+        RESPType::Integer(i) => Ok(*i),
+        other => Err(invalid("integer", other)),
+    }
+}
+
+/// Accepts doubles and integers as well as strings holding a decimal
+/// number, which is how RESP2 servers send floating point values.
+pub(crate) fn float(value: &RESPType) -> Result<f64> {
+    match value.without_attributes() {
+        RESPType::Double(d) => Ok(*d),
+        RESPType::Integer(i) => Ok(*i as f64),
+        other => {
+            let s = string(other)?;
+            s.parse::<f64>()
+                .map_err(|_| Error::InvalidReply(format!("expected number, got {:?}", s)))
+        }
+    }
+}
+
+pub(crate) fn array(value: &RESPType) -> Result<&[RESPType]> {
+    match value.without_attributes() {
+        RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => Ok(items),
+        other => Err(invalid("array", other)),
+    }
+}
+
+pub(crate) fn strings(value: &RESPType) -> Result<Vec<String>> {
+    array(value)?.iter().map(string).collect()
+}
+
+/// Splits a flat `[key, value, key, value, ...]` reply into pairs. RESP3
+/// maps are accepted as well.
+pub(crate) fn pairs(value: &RESPType) -> Result<Vec<(&RESPType, &RESPType)>> {
+    if let RESPType::Map(entries) = value.without_attributes() {
+        return Ok(entries.iter().map(|(k, v)| (k, v)).collect());
+    }
+    let items = array(value)?;
+    if items.len() % 2 != 0 {
+        return Err(Error::InvalidReply(format!(
+            "expected key/value pairs, got {} elements",
+            items.len()
+        )));
+    }
+    Ok(items.chunks(2).map(|kv| (&kv[0], &kv[1])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RESPKind;
+
+    #[test]
+    fn reports_expected_and_actual_types() {
+        match integer(&RESPType::BulkString(b"1".to_vec())) {
+            Err(Error::TypeMismatch {
+                expected: "integer",
+                actual: RESPKind::BulkString,
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let err = array(&RESPType::Null).unwrap_err();
+        assert_eq!(err.to_string(), "Expected array, got null");
+    }
+
+    #[test]
+    fn maps_wrongtype_errors() {
+        let reply = RESPType::Error(
+            "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+        );
+        match strings(&reply) {
+            Err(Error::WrongType(msg)) => assert!(msg.starts_with("WRONGTYPE")),
+            other => panic!("unexpected {:?}", other),
+        }
+        match integer(&RESPType::Error("ERR no such key".to_string())) {
+            Err(Error::TypeMismatch {
+                actual: RESPKind::Error,
+                ..
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pairs_accepts_maps() {
+        let map = RESPType::Map(vec![(RESPType::Integer(1), RESPType::Null)]);
+        assert_eq!(
+            pairs(&map).unwrap(),
+            vec![(&RESPType::Integer(1), &RESPType::Null)]
+        );
+        let set = RESPType::Set(vec![RESPType::BulkString(b"x".to_vec())]);
+        assert_eq!(strings(&set).unwrap(), vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn helpers_skip_attributes() {
+        let value = RESPType::Attributed {
+            attrs: vec![(RESPType::Integer(0), RESPType::Null)],
+            value: Box::new(RESPType::Integer(7)),
+        };
+        assert_eq!(integer(&value).unwrap(), 7);
+        assert_eq!(float(&value).unwrap(), 7.0);
+    }
+}
@@ -0,0 +1,183 @@
+//! OBJECT ENCODING, OBJECT FREQ, OBJECT IDLETIME and DEBUG OBJECT replies.
+
+use super::{integer, string};
+use crate::{Error, RESPType, Result};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The internal representation of a value, as reported by OBJECT ENCODING.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ObjectEncoding {
+    Raw,
+    Int,
+    EmbStr,
+    ListPack,
+    ListPackEx,
+    QuickList,
+    LinkedList,
+    ZipList,
+    ZipMap,
+    IntSet,
+    HashTable,
+    SkipList,
+    Stream,
+    /// An encoding not listed above, as sent by the server.
+    Other(String),
+}
+
+const ENCODINGS: &[(&str, ObjectEncoding)] = &[
+    ("raw", ObjectEncoding::Raw),
+    ("int", ObjectEncoding::Int),
+    ("embstr", ObjectEncoding::EmbStr),
+    ("listpack", ObjectEncoding::ListPack),
+    ("listpackex", ObjectEncoding::ListPackEx),
+    ("quicklist", ObjectEncoding::QuickList),
+    ("linkedlist", ObjectEncoding::LinkedList),
+    ("ziplist", ObjectEncoding::ZipList),
+    ("zipmap", ObjectEncoding::ZipMap),
+    ("intset", ObjectEncoding::IntSet),
+    ("hashtable", ObjectEncoding::HashTable),
+    ("skiplist", ObjectEncoding::SkipList),
+    ("stream", ObjectEncoding::Stream),
+];
+
+impl ObjectEncoding {
+    pub fn parse(name: &str) -> ObjectEncoding {
+        ENCODINGS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, encoding)| encoding.clone())
+            .unwrap_or_else(|| ObjectEncoding::Other(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ObjectEncoding::Other(name) => name,
+            known => ENCODINGS
+                .iter()
+                .find(|(_, encoding)| encoding == known)
+                .map(|(name, _)| *name)
+                .unwrap_or(""),
+        }
+    }
+}
+
+impl fmt::Display for ObjectEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Decodes the OBJECT ENCODING reply. Returns `None` if the key does not
+/// exist.
+pub fn parse_encoding(value: &RESPType) -> Result<Option<ObjectEncoding>> {
+    match value.without_attributes() {
+        RESPType::Null => Ok(None),
+        other => Ok(Some(ObjectEncoding::parse(&string(other)?))),
+    }
+}
+
+/// Decodes the OBJECT FREQ reply. Returns `None` if the key does not exist.
+pub fn parse_freq(value: &RESPType) -> Result<Option<i64>> {
+    optional_integer(value)
+}
+
+/// Decodes the OBJECT IDLETIME reply, in seconds. Returns `None` if the key
+/// does not exist.
+pub fn parse_idletime(value: &RESPType) -> Result<Option<i64>> {
+    optional_integer(value)
+}
+
+fn optional_integer(value: &RESPType) -> Result<Option<i64>> {
+    match value.without_attributes() {
+        RESPType::Null => Ok(None),
+        other => integer(other).map(Some),
+    }
+}
+
+/// The DEBUG OBJECT reply, e.g. `Value at:0x7f01 refcount:1
+/// encoding:embstr serializedlength:4 lru:2791 lru_seconds_idle:7`. Fields
+/// this type does not know about are kept in `other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugObject {
+    pub address: String,
+    pub refcount: i64,
+    pub encoding: ObjectEncoding,
+    pub serialized_length: i64,
+    pub lru: i64,
+    pub lru_seconds_idle: i64,
+    pub other: HashMap<String, String>,
+}
+
+/// Decodes the DEBUG OBJECT reply.
+pub fn parse_debug_object(value: &RESPType) -> Result<DebugObject> {
+    let line = string(value)?;
+    let bad = || Error::InvalidReply(format!("bad DEBUG OBJECT reply {:?}", line));
+    let mut fields: HashMap<String, String> = HashMap::new();
+    for token in line.split_whitespace() {
+        if let Some((key, val)) = token.split_once(':') {
+            fields.insert(key.to_string(), val.to_string());
+        }
+    }
+    let mut take_int = |key: &str| -> Result<i64> {
+        fields
+            .remove(key)
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(bad)
+    };
+    let refcount = take_int("refcount")?;
+    let serialized_length = take_int("serializedlength")?;
+    let lru = take_int("lru")?;
+    let lru_seconds_idle = take_int("lru_seconds_idle")?;
+    Ok(DebugObject {
+        address: fields.remove("at").ok_or_else(bad)?,
+        refcount,
+        encoding: ObjectEncoding::parse(&fields.remove("encoding").ok_or_else(bad)?),
+        serialized_length,
+        lru,
+        lru_seconds_idle,
+        other: fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_object_replies() {
+        let encoding = parse_encoding(&RESPType::BulkString(b"listpack".to_vec())).unwrap();
+        assert_eq!(encoding, Some(ObjectEncoding::ListPack));
+        assert_eq!(parse_encoding(&RESPType::Null).unwrap(), None);
+        assert_eq!(
+            ObjectEncoding::parse("future"),
+            ObjectEncoding::Other("future".to_string())
+        );
+        assert_eq!(parse_freq(&RESPType::Integer(5)).unwrap(), Some(5));
+        assert_eq!(parse_idletime(&RESPType::Null).unwrap(), None);
+
+        let null = RESPType::Attributed {
+            attrs: Vec::new(),
+            value: Box::new(RESPType::Null),
+        };
+        assert_eq!(parse_encoding(&null).unwrap(), None);
+        assert_eq!(parse_freq(&null).unwrap(), None);
+    }
+
+    #[test]
+    fn parses_debug_object() {
+        let reply = RESPType::SimpleString(
+            "Value at:0x7f5c2e0e3d40 refcount:1 encoding:quicklist serializedlength:19 \
+             lru:8903002 lru_seconds_idle:12 ql_nodes:1"
+                .to_string(),
+        );
+        let obj = parse_debug_object(&reply).unwrap();
+        assert_eq!(obj.address, "0x7f5c2e0e3d40");
+        assert_eq!(obj.refcount, 1);
+        assert_eq!(obj.encoding, ObjectEncoding::QuickList);
+        assert_eq!(obj.serialized_length, 19);
+        assert_eq!(obj.lru_seconds_idle, 12);
+        assert_eq!(obj.other.get("ql_nodes").map(String::as_str), Some("1"));
+        assert!(parse_debug_object(&RESPType::SimpleString("Value".to_string())).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! PUBSUB CHANNELS, NUMSUB and NUMPAT replies, and their SHARD variants.
+
+use super::{integer, pairs, string, strings};
+use crate::{RESPType, Result};
+
+/// Decodes the PUBSUB CHANNELS (or SHARDCHANNELS) reply.
+pub fn parse_channels(value: &RESPType) -> Result<Vec<String>> {
+    strings(value)
+}
+
+/// Decodes the PUBSUB NUMSUB (or SHARDNUMSUB) reply, which alternates
+/// channel names and subscriber counts, into pairs in request order.
+pub fn parse_numsub(value: &RESPType) -> Result<Vec<(String, i64)>> {
+    pairs(value)?
+        .into_iter()
+        .map(|(channel, count)| Ok((string(channel)?, integer(count)?)))
+        .collect()
+}
+
+/// Decodes the PUBSUB NUMPAT reply.
+pub fn parse_numpat(value: &RESPType) -> Result<i64> {
+    integer(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bulk;
+
+    #[test]
+    fn parses_channels() {
+        let reply = RESPType::Array(vec![bulk("news"), bulk("sport")]);
+        assert_eq!(parse_channels(&reply).unwrap(), vec!["news", "sport"]);
+    }
+
+    #[test]
+    fn parses_numsub() {
+        let reply = RESPType::Array(vec![
+            bulk("news"),
+            RESPType::Integer(2),
+            bulk("sport"),
+            RESPType::Integer(0),
+        ]);
+        assert_eq!(
+            parse_numsub(&reply).unwrap(),
+            vec![("news".to_string(), 2), ("sport".to_string(), 0)]
+        );
+        assert!(parse_numsub(&RESPType::Array(vec![bulk("news")])).is_err());
+    }
+
+    #[test]
+    fn parses_numpat() {
+        assert_eq!(parse_numpat(&RESPType::Integer(3)).unwrap(), 3);
+    }
+}
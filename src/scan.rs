@@ -0,0 +1,52 @@
+//! Word-at-a-time byte search for line terminators.
+
+use std::convert::TryInto;
+
+const LO: u64 = 0x0101_0101_0101_0101;
+const HI: u64 = 0x8080_8080_8080_8080;
+
+/// Returns the index of the first `needle` in `haystack`, checking eight
+/// bytes per step.
+pub(crate) fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let repeated = LO * needle as u64;
+    let mut offset = 0;
+    for chunk in haystack.chunks_exact(8) {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap()) ^ repeated;
+        // Nonzero exactly when some byte of `word` is zero.
+        if word.wrapping_sub(LO) & !word & HI != 0 {
+            break;
+        }
+        offset += 8;
+    }
+    haystack[offset..]
+        .iter()
+        .position(|&b| b == needle)
+        .map(|i| offset + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_naive_search() {
+        let mut state: u32 = 1;
+        for len in 0..64 {
+            let haystack: Vec<u8> = (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    [b'a', b'\r', b'\n', 0x80, 0xff, 0x0a ^ 0x80][(state >> 16) as usize % 6]
+                })
+                .collect();
+            for &needle in b"\n\r\x80z" {
+                assert_eq!(
+                    find_byte(needle, &haystack),
+                    haystack.iter().position(|&b| b == needle),
+                    "{:?} in {:?}",
+                    needle,
+                    haystack
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,656 @@
+use crate::scan;
+use crate::{Error, ParseOptions, RESPKind, Result};
+
+/// Returns the encoded length of the first frame in `buf` without
+/// materializing it. Only the frame headers are inspected, so this is much
+/// cheaper than parsing.
+pub fn frame_len(buf: &[u8]) -> Result<usize> {
+    let mut pos = 0;
+    skip_frame(buf, &mut pos)?;
+    Ok(pos)
+}
+
+/// Iterator over the encoded frames in a buffer, yielding one slice per
+/// frame. Iteration stops after the first error.
+pub struct Frames<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    failed: bool,
+}
+
+pub fn frames(buf: &[u8]) -> Frames<'_> {
+    Frames {
+        buf,
+        pos: 0,
+        failed: false,
+    }
+}
+
+impl<'a> Frames<'a> {
+    /// Offset of the next frame in the buffer.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Result<&'a [u8]>> {
+        if self.failed || self.pos >= self.buf.len() {
+            return None;
+        }
+        let start = self.pos;
+        match skip_frame(self.buf, &mut self.pos) {
+            Ok(()) => Some(Ok(&self.buf[start..self.pos])),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+fn skip_frame(buf: &[u8], pos: &mut usize) -> Result<()> {
+    let mut scanner = Scanner::new(buf, *pos, false);
+    let result = scanner.skip_frame().map(|_| ());
+    *pos = scanner.pos;
+    result
+}
+
+/// Walks frame headers without materializing values. When collecting
+/// warnings it also validates integer frames and records constructs that
+/// only a lenient parser accepts.
+pub(crate) struct Scanner<'a> {
+    buf: &'a [u8],
+    pub(crate) pos: usize,
+    pub(crate) warnings: Option<Vec<Warning>>,
+    /// Accept lengths with a `+` sign, leading zeros or surrounding
+    /// whitespace, as a lenient parser does. Set when collecting warnings.
+    pub(crate) lenient: bool,
+    /// Fail with [`Error::NestingTooDeep`] past this many levels.
+    pub(crate) max_depth: Option<usize>,
+    stack: Vec<Level>,
+    kind: Option<RESPKind>,
+}
+
+/// Progress through a partly scanned frame, so that scanning can resume
+/// where it stopped once more of the frame has arrived.
+#[derive(Debug, Default)]
+pub(crate) struct ScanState {
+    pub(crate) pos: usize,
+    stack: Vec<Level>,
+    kind: Option<RESPKind>,
+}
+
+/// An aggregate being skipped.
+#[derive(Debug)]
+struct Level {
+    /// Frames left to skip; for a streamed aggregate, frames left in the
+    /// current element.
+    left: u64,
+    /// Frames per element of a streamed aggregate, which ends at a `.`
+    /// marker rather than after a count.
+    streamed: Option<u64>,
+    /// Frames left after the end marker, i.e. the value of a streamed
+    /// attribute block.
+    after: u64,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(buf: &'a [u8], pos: usize, collect_warnings: bool) -> Scanner<'a> {
+        Scanner {
+            buf,
+            pos,
+            warnings: if collect_warnings {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            lenient: collect_warnings,
+            max_depth: None,
+            stack: Vec::new(),
+            kind: None,
+        }
+    }
+
+    /// Continues the scan that `state` was suspended from.
+    pub(crate) fn resume(buf: &'a [u8], state: ScanState, collect_warnings: bool) -> Scanner<'a> {
+        Scanner {
+            stack: state.stack,
+            kind: state.kind,
+            ..Scanner::new(buf, state.pos, collect_warnings)
+        }
+    }
+
+    pub(crate) fn suspend(self) -> ScanState {
+        ScanState {
+            pos: self.pos,
+            stack: self.stack,
+            kind: self.kind,
+        }
+    }
+
+    /// Skips one frame and returns its kind.
+    ///
+    /// Aggregates are tracked on an explicit stack, so deep nesting cannot
+    /// overflow the call stack. Each header is consumed completely or not
+    /// at all, so after an error the scanner stops at the header that
+    /// failed and, if it was incomplete, can be resumed over a longer
+    /// buffer.
+    pub(crate) fn skip_frame(&mut self) -> Result<RESPKind> {
+        loop {
+            // Find the level the next frame belongs to, closing finished ones.
+            while let Some(level) = self.stack.last_mut() {
+                if level.left > 0 {
+                    break;
+                }
+                match level.streamed {
+                    Some(width) => {
+                        if self.pos < self.buf.len() && self.buf[self.pos] != b'.' {
+                            level.left = width;
+                            break;
+                        }
+                        self.atomic(Scanner::skip_end_marker)?;
+                        let level = self.stack.last_mut().expect("streamed level");
+                        level.streamed = None;
+                        level.left = level.after;
+                    }
+                    None => {
+                        self.stack.pop();
+                    }
+                }
+            }
+            if self.stack.is_empty() && self.kind.is_some() {
+                return Ok(self.kind.take().expect("skipped a frame"));
+            }
+            let depth = self.stack.len();
+            let max_depth = self.max_depth;
+            let (frame_kind, level) = self.atomic(|scanner| {
+                let offset = scanner.pos as u64;
+                let (kind, level) = scanner.skip_header()?;
+                match max_depth {
+                    Some(limit) if level.is_some() && depth >= limit => {
+                        Err(Error::NestingTooDeep { limit, offset })
+                    }
+                    _ => Ok((kind, level)),
+                }
+            })?;
+            if let Some(parent) = self.stack.last_mut() {
+                parent.left -= 1;
+            }
+            self.kind.get_or_insert(frame_kind);
+            self.stack.extend(level);
+        }
+    }
+
+    /// Runs `f`, rewinding to where it started if it fails.
+    fn atomic<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let pos = self.pos;
+        let warned = self.warnings.as_ref().map_or(0, Vec::len);
+        let result = f(self);
+        if result.is_err() {
+            self.pos = pos;
+            if let Some(warnings) = self.warnings.as_mut() {
+                warnings.truncate(warned);
+            }
+        }
+        result
+    }
+
+    /// Consumes the `.` end marker of a streamed aggregate.
+    fn skip_end_marker(&mut self) -> Result<()> {
+        match self.buf.get(self.pos) {
+            Some(b'.') => {
+                self.pos += 1;
+                let start = self.pos;
+                match self.read_line()?.first() {
+                    None => Ok(()),
+                    Some(&b) => Err(Error::UnexpectedToken {
+                        byte: b,
+                        offset: start as u64,
+                    }),
+                }
+            }
+            _ => Err(Error::Incomplete { needed: None }),
+        }
+    }
+
+    /// Skips a scalar frame, or the header of an aggregate along with the
+    /// level its elements are skipped at.
+    fn skip_header(&mut self) -> Result<(RESPKind, Option<Level>)> {
+        let marker = *self
+            .buf
+            .get(self.pos)
+            .ok_or(Error::Incomplete { needed: None })?;
+        let start = self.pos;
+        self.pos += 1;
+        // Where the length line of a sized frame starts.
+        let offset = self.pos as u64;
+        let invalid_length = |length| Err(Error::InvalidLength { length, offset });
+        let aggregate = |kind, left, streamed, after| {
+            Ok((
+                kind,
+                Some(Level {
+                    left,
+                    streamed,
+                    after,
+                }),
+            ))
+        };
+        match marker {
+            b'+' | b'-' => {
+                self.read_line()?;
+                Ok((
+                    if marker == b'+' {
+                        RESPKind::SimpleString
+                    } else {
+                        RESPKind::Error
+                    },
+                    None,
+                ))
+            }
+            // Attributes are followed by the value they apply to, skipped as
+            // one more frame of the same level.
+            b'|' => match self.read_header()? {
+                None => aggregate(RESPKind::Attributed, 0, Some(2), 1),
+                Some(len) => match len.checked_mul(2) {
+                    Some(count) if len >= 0 => {
+                        aggregate(RESPKind::Attributed, count as u64 + 1, None, 0)
+                    }
+                    _ => invalid_length(len),
+                },
+            },
+            b'%' | b'~' | b'>' => {
+                let width = if marker == b'%' { 2 } else { 1 };
+                let kind = match marker {
+                    b'%' => RESPKind::Map,
+                    b'~' => RESPKind::Set,
+                    _ => RESPKind::Push,
+                };
+                match self.read_header()? {
+                    None => aggregate(kind, 0, Some(width), 0),
+                    Some(len) if len < 0 => invalid_length(len),
+                    Some(len) => match len.checked_mul(width as i64) {
+                        Some(count) => aggregate(kind, count as u64, None, 0),
+                        None => invalid_length(len),
+                    },
+                }
+            }
+            b',' | b'#' | b'(' => {
+                self.read_line()?;
+                Ok((
+                    match marker {
+                        b',' => RESPKind::Double,
+                        b'#' => RESPKind::Boolean,
+                        _ => RESPKind::BigNumber,
+                    },
+                    None,
+                ))
+            }
+            b'_' => {
+                self.read_line()?;
+                Ok((RESPKind::Null, None))
+            }
+            b':' => {
+                let line_start = self.pos;
+                let line = self.read_line()?;
+                if self.warnings.is_some() {
+                    match self.check_integer(&line, line_start) {
+                        Ok(_) => {}
+                        Err(Error::BadInteger { .. }) => {
+                            self.warn(line_start, WarningKind::IntegerOverflow)
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok((RESPKind::Integer, None))
+            }
+            b'$' | b'=' | b'!' => {
+                let len = match self.read_header()? {
+                    Some(len) => len,
+                    None if marker == b'$' => {
+                        self.skip_string_chunks()?;
+                        return Ok((RESPKind::BulkString, None));
+                    }
+                    None => {
+                        return Err(Error::MalformedInteger {
+                            value: "?".to_string(),
+                            offset,
+                        })
+                    }
+                };
+                if len < -1 || len < 0 && marker != b'$' {
+                    return invalid_length(len);
+                }
+                if len < 0 {
+                    self.warn(start, WarningKind::Resp2Null);
+                    return Ok((RESPKind::Null, None));
+                }
+                self.skip_blob(len as usize)?;
+                Ok((
+                    match marker {
+                        b'$' => RESPKind::BulkString,
+                        b'=' => RESPKind::Verbatim,
+                        _ => RESPKind::BulkError,
+                    },
+                    None,
+                ))
+            }
+            b'*' => match self.read_header()? {
+                None => aggregate(RESPKind::Array, 0, Some(1), 0),
+                Some(len) if len < -1 => invalid_length(len),
+                Some(len) if len < 0 => {
+                    self.warn(start, WarningKind::Resp2Null);
+                    Ok((RESPKind::Null, None))
+                }
+                Some(len) => aggregate(RESPKind::Array, len as u64, None, 0),
+            },
+            b => Err(Error::UnexpectedToken {
+                byte: b,
+                offset: start as u64,
+            }),
+        }
+    }
+
+    /// Skips a blob payload and its CRLF trailer.
+    fn skip_blob(&mut self, len: usize) -> Result<()> {
+        self.pos += len;
+        if self.pos > self.buf.len() {
+            return Err(Error::Incomplete {
+                needed: Some(self.pos - self.buf.len() + 2),
+            });
+        }
+        let trailer_start = self.pos;
+        if !self.read_line()?.is_empty() {
+            self.warn(trailer_start, WarningKind::TrailingBytes);
+        }
+        Ok(())
+    }
+
+    /// Skips the `;len` chunks of a streamed string up to the final `;0`.
+    fn skip_string_chunks(&mut self) -> Result<()> {
+        loop {
+            match self.buf.get(self.pos) {
+                Some(b';') => self.pos += 1,
+                Some(&b) => {
+                    return Err(Error::UnexpectedToken {
+                        byte: b,
+                        offset: self.pos as u64,
+                    })
+                }
+                None => return Err(Error::Incomplete { needed: None }),
+            }
+            let offset = self.pos as u64;
+            match self.read_len()? {
+                0 => return Ok(()),
+                len if len > 0 => self.skip_blob(len as usize)?,
+                length => return Err(Error::InvalidLength { length, offset }),
+            }
+        }
+    }
+
+    fn warn(&mut self, offset: usize, kind: WarningKind) {
+        if let Some(warnings) = self.warnings.as_mut() {
+            warnings.push(Warning { offset, kind });
+        }
+    }
+
+    /// Reads up to the next line feed and returns the line with every
+    /// carriage return removed. Bare line feeds and stray carriage returns
+    /// are accepted, and only recorded as warnings.
+    fn read_line(&mut self) -> Result<Vec<u8>> {
+        let rest = &self.buf[self.pos..];
+        let end = scan::find_byte(b'\n', rest).ok_or(Error::Incomplete { needed: None })?;
+        let line = &rest[..end];
+        let start = self.pos;
+        self.pos += end + 1;
+        if self.warnings.is_some() {
+            match line.split_last() {
+                Some((b'\r', body)) => {
+                    if let Some(i) = body.iter().position(|&b| b == b'\r') {
+                        self.warn(start + i, WarningKind::StrayCarriageReturn);
+                    }
+                }
+                _ => self.warn(start + end, WarningKind::BareLineFeed),
+            }
+        }
+        Ok(line.iter().cloned().filter(|&b| b != b'\r').collect())
+    }
+
+    /// Reads a length line, returning `None` for the `?` that introduces a
+    /// streamed frame.
+    fn read_header(&mut self) -> Result<Option<i64>> {
+        let line_start = self.pos;
+        let line = self.read_line()?;
+        if line == b"?" {
+            return Ok(None);
+        }
+        self.parse_len(line, line_start).map(Some)
+    }
+
+    fn read_len(&mut self) -> Result<i64> {
+        let line_start = self.pos;
+        let line = self.read_line()?;
+        self.parse_len(line, line_start)
+    }
+
+    fn parse_len(&mut self, line: Vec<u8>, line_start: usize) -> Result<i64> {
+        if self.lenient {
+            self.check_integer(&line, line_start)
+        } else {
+            let line = utf8_line(line, line_start)?;
+            ParseOptions::strict().parse_int(&line, line_start as u64)
+        }
+    }
+
+    /// Parses an integer line leniently, recording a warning for each
+    /// deviation from the strict grammar.
+    fn check_integer(&mut self, line: &[u8], offset: usize) -> Result<i64> {
+        let s = utf8_line(line.to_vec(), offset)?;
+        let value = ParseOptions::lenient().parse_int(&s, offset as u64)?;
+        let trimmed = s.trim_matches(|c| c == ' ' || c == '\t');
+        if trimmed.len() != s.len() {
+            self.warn(offset, WarningKind::IntegerWhitespace);
+        }
+        let digits = match trimmed.as_bytes().first() {
+            Some(b'+') => {
+                self.warn(offset, WarningKind::IntegerPlusSign);
+                &trimmed[1..]
+            }
+            Some(b'-') => &trimmed[1..],
+            _ => trimmed,
+        };
+        if digits.len() > 1 && digits.starts_with('0') {
+            self.warn(offset, WarningKind::IntegerLeadingZeros);
+        }
+        Ok(value)
+    }
+}
+
+fn utf8_line(line: Vec<u8>, offset: usize) -> Result<String> {
+    String::from_utf8(line).map_err(|source| Error::BadString {
+        offset: offset as u64,
+        source,
+    })
+}
+
+/// A construct that only a lenient parser accepts, or a deprecated form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Warning {
+    pub offset: usize,
+    pub kind: WarningKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A line terminated by `\n` without the preceding `\r`.
+    BareLineFeed,
+    /// A `\r` inside a line, which a parser with loose line endings drops.
+    StrayCarriageReturn,
+    /// Bytes between a bulk payload and its CRLF trailer.
+    TrailingBytes,
+    IntegerPlusSign,
+    IntegerLeadingZeros,
+    IntegerWhitespace,
+    /// An integer reply too large for an `i64`, only accepted as a big
+    /// number.
+    IntegerOverflow,
+    /// A RESP2 `$-1` or `*-1` null, which RESP3 replaces with `_`.
+    Resp2Null,
+}
+
+/// Splits `buf` into frames sequentially, then decodes the frames on all
+/// available cores. Results are returned in stream order; if the buffer
+/// ends with a malformed or truncated frame, the last result is the
+/// splitting error.
+#[cfg(feature = "parallel")]
+pub fn decode_frames_parallel(buf: &[u8]) -> Vec<Result<crate::RESPType>> {
+    let mut split_error = None;
+    let mut slices = Vec::new();
+    for frame in frames(buf) {
+        match frame {
+            Ok(slice) => slices.push(slice),
+            Err(err) => split_error = Some(err),
+        }
+    }
+
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk_size = slices.len().div_ceil(threads).max(1);
+    let mut results: Vec<Result<crate::RESPType>> = Vec::with_capacity(slices.len() + 1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = slices
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|slice| crate::Parser::buffered(*slice).parse_next())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            results.extend(handle.join().expect("decoder thread panicked"));
+        }
+    });
+    results.extend(split_error.map(Err));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &[u8] = b"+OK\r\n*2\r\n$3\r\nfoo\r\n:1\r\n$-1\r\n*0\r\n";
+
+    #[test]
+    fn measures_frames() {
+        assert_eq!(frame_len(b":1\r\n:2\r\n").unwrap(), 4);
+        assert_eq!(frame_len(b"*2\r\n$1\r\n\n\r\n*-1\r\n").unwrap(), 16);
+        assert_eq!(frame_len(b"*3\r\n,1.5\r\n#t\r\n_\r\n").unwrap(), 17);
+        assert_eq!(frame_len(b"%1\r\n+k\r\n~2\r\n:1\r\n:2\r\n").unwrap(), 20);
+        assert_eq!(frame_len(b"|1\r\n+a\r\n:1\r\n:2\r\n:3\r\n").unwrap(), 16);
+        assert_eq!(frame_len(b"$?\r\n;2\r\nab\r\n;0\r\n:1\r\n").unwrap(), 16);
+        assert!(frame_len(b"$?\r\n;2\r\nab\r\n").is_err());
+        assert_eq!(frame_len(b"*?\r\n:1\r\n~?\r\n.\r\n.\r\n").unwrap(), 18);
+        assert_eq!(frame_len(b"%?\r\n+a\r\n:1\r\n.\r\n").unwrap(), 15);
+        assert!(frame_len(b"*?\r\n:1\r\n").is_err());
+    }
+
+    #[test]
+    fn splits_stream_into_frames() {
+        let have: Vec<&[u8]> = frames(STREAM).map(|f| f.unwrap()).collect();
+        let want: Vec<&[u8]> = vec![
+            b"+OK\r\n",
+            b"*2\r\n$3\r\nfoo\r\n:1\r\n",
+            b"$-1\r\n",
+            b"*0\r\n",
+        ];
+        assert_eq!(have, want);
+    }
+
+    #[test]
+    fn stops_at_truncated_frame() {
+        let mut it = frames(b":1\r\n$5\r\nab");
+        assert!(it.next().unwrap().is_ok());
+        match it.next() {
+            Some(Err(Error::Incomplete { needed: Some(5) })) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        assert_eq!(frame_len(b"$-1\r\n").unwrap(), 5);
+        for input in [&b"$-2\r\n"[..], b"*-5\r\n", b"%-1\r\n", b"=-1\r\n"] {
+            match frame_len(input) {
+                Err(Error::InvalidLength { offset: 1, .. }) => {}
+                other => panic!("unexpected {:?} for {:?}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_lengths() {
+        for input in [
+            &b"|9223372036854775807\r\n"[..],
+            b"%9223372036854775807\r\n",
+        ] {
+            match frame_len(input) {
+                Err(Error::InvalidLength {
+                    length: i64::MAX,
+                    offset: 1,
+                }) => {}
+                other => panic!("unexpected {:?} for {:?}", other, input),
+            }
+        }
+        assert!(matches!(
+            crate::Decoder::new().feed(b"|9223372036854775807\r\n"),
+            Err(Error::InvalidLength { .. })
+        ));
+    }
+
+    #[test]
+    fn deep_nesting_does_not_recurse() {
+        let deep = "*1\r\n".repeat(1_000_000);
+        assert!(matches!(
+            frame_len(deep.as_bytes()),
+            Err(Error::Incomplete { needed: None })
+        ));
+        let complete = deep + ":1\r\n";
+        assert_eq!(frame_len(complete.as_bytes()).unwrap(), complete.len());
+        let report = crate::validate::validate_report(complete.as_bytes());
+        assert_eq!(report.frames[0].kind, RESPKind::Array);
+        assert_eq!(
+            frame_len(b"|?\r\n+a\r\n:1\r\n.\r\n:2\r\n:3\r\n").unwrap(),
+            19
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_marker() {
+        assert!(frame_len(b"?x\r\n").is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn decodes_in_parallel_in_order() {
+        use crate::RESPType;
+
+        let mut buf = Vec::new();
+        for i in 0..1000 {
+            buf.extend(format!(":{}\r\n", i).as_bytes());
+        }
+        buf.extend(b"$3\r\nab");
+        let results = decode_frames_parallel(&buf);
+        assert_eq!(results.len(), 1001);
+        for (i, r) in results[..1000].iter().enumerate() {
+            assert_eq!(r.as_ref().unwrap(), &RESPType::Integer(i as i64));
+        }
+        assert!(results[1000].is_err());
+    }
+}
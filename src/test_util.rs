@@ -0,0 +1,7 @@
+//! Fixtures shared by the unit tests.
+
+use crate::RESPType;
+
+pub(crate) fn bulk(s: &str) -> RESPType {
+    RESPType::BulkString(s.as_bytes().to_vec())
+}
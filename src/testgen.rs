@@ -0,0 +1,280 @@
+//! Deterministic generation of random, valid RESP values and streams.
+
+use crate::encode;
+use crate::{RESPKind, RESPType};
+
+/// Generates random values from a seed. The same seed and settings always
+/// produce the same output, on every platform.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+    weights: [(RESPKind, u32); 14],
+    max_depth: usize,
+    max_elements: usize,
+    max_bytes: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            state: seed,
+            weights: [
+                (RESPKind::SimpleString, 1),
+                (RESPKind::Error, 1),
+                (RESPKind::Integer, 1),
+                (RESPKind::BulkString, 1),
+                (RESPKind::Null, 1),
+                (RESPKind::Array, 1),
+                (RESPKind::Map, 0),
+                (RESPKind::Set, 0),
+                (RESPKind::Push, 0),
+                (RESPKind::Double, 0),
+                (RESPKind::Boolean, 0),
+                (RESPKind::BigNumber, 0),
+                (RESPKind::Verbatim, 0),
+                (RESPKind::BulkError, 0),
+            ],
+            max_depth: 3,
+            max_elements: 8,
+            max_bytes: 64,
+        }
+    }
+
+    /// Relative frequency of `kind`; 0 disables it. The RESP2 kinds start
+    /// at 1 and the RESP3 kinds at 0. Aggregates are not generated below
+    /// the maximum depth.
+    ///
+    /// # Panics
+    ///
+    /// If `kind` is [`RESPKind::Attributed`] or [`RESPKind::Extension`],
+    /// which are never generated.
+    pub fn weight(mut self, kind: RESPKind, weight: u32) -> Generator {
+        match self.weights.iter_mut().find(|entry| entry.0 == kind) {
+            Some(entry) => entry.1 = weight,
+            None => panic!("cannot generate {} values", kind),
+        }
+        self
+    }
+
+    /// Sets every RESP3 kind to weight 1, alongside the RESP2 kinds.
+    pub fn resp3(self) -> Generator {
+        [
+            RESPKind::Map,
+            RESPKind::Set,
+            RESPKind::Push,
+            RESPKind::Double,
+            RESPKind::Boolean,
+            RESPKind::BigNumber,
+            RESPKind::Verbatim,
+            RESPKind::BulkError,
+        ]
+        .iter()
+        .fold(self, |gen, &kind| gen.weight(kind, 1))
+    }
+
+    /// Maximum nesting depth of aggregates.
+    pub fn max_depth(mut self, depth: usize) -> Generator {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Maximum number of elements per aggregate, counting map entries.
+    pub fn max_elements(mut self, n: usize) -> Generator {
+        self.max_elements = n;
+        self
+    }
+
+    /// Maximum payload length of strings.
+    pub fn max_bytes(mut self, n: usize) -> Generator {
+        self.max_bytes = n;
+        self
+    }
+
+    pub fn value(&mut self) -> RESPType {
+        self.value_at(0)
+    }
+
+    /// Encodes `frames` random values back to back, as a pipelining client
+    /// or server would send them.
+    pub fn stream(&mut self, frames: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for _ in 0..frames {
+            let value = self.value();
+            encode::write_value(&mut buf, &value).expect("writing to a Vec cannot fail");
+        }
+        buf
+    }
+
+    /// splitmix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..=max`.
+    fn below(&mut self, max: usize) -> usize {
+        (self.next_u64() % (max as u64 + 1)) as usize
+    }
+
+    fn pick_kind(&mut self, depth: usize) -> RESPKind {
+        let nest = depth < self.max_depth;
+        let allowed = |kind: RESPKind| {
+            nest || !matches!(
+                kind,
+                RESPKind::Array | RESPKind::Map | RESPKind::Set | RESPKind::Push
+            )
+        };
+        let weights = self.weights;
+        let total: u32 = weights
+            .iter()
+            .filter(|(k, _)| allowed(*k))
+            .map(|(_, w)| w)
+            .sum();
+        if total == 0 {
+            return RESPKind::Null;
+        }
+        let mut n = (self.next_u64() % u64::from(total)) as u32;
+        for &(kind, weight) in weights.iter().filter(|(k, _)| allowed(*k)) {
+            if n < weight {
+                return kind;
+            }
+            n -= weight;
+        }
+        unreachable!()
+    }
+
+    fn value_at(&mut self, depth: usize) -> RESPType {
+        match self.pick_kind(depth) {
+            RESPKind::SimpleString => RESPType::SimpleString(self.line()),
+            RESPKind::Error => RESPType::Error(format!("ERR {}", self.line())),
+            RESPKind::Integer => RESPType::Integer(self.next_u64() as i64),
+            RESPKind::BulkString => RESPType::BulkString(self.bytes()),
+            RESPKind::Array => RESPType::Array(self.items(depth)),
+            RESPKind::Map => {
+                let len = self.below(self.max_elements);
+                RESPType::Map(
+                    (0..len)
+                        .map(|_| (self.value_at(depth + 1), self.value_at(depth + 1)))
+                        .collect(),
+                )
+            }
+            RESPKind::Set => RESPType::Set(self.items(depth)),
+            RESPKind::Push => RESPType::Push(self.items(depth)),
+            RESPKind::Double => RESPType::Double(f64::from_bits(self.next_u64())),
+            RESPKind::Boolean => RESPType::Boolean(self.below(1) == 0),
+            RESPKind::BigNumber => {
+                let sign = if self.below(1) == 0 { "" } else { "-" };
+                let digits: String = (0..=self.below(self.max_bytes))
+                    .map(|_| (b'0' + (self.next_u64() % 10) as u8) as char)
+                    .collect();
+                RESPType::BigNumber(format!("{}{}", sign, digits))
+            }
+            RESPKind::Verbatim => {
+                let formats = [*b"txt", *b"mkd"];
+                RESPType::Verbatim {
+                    format: formats[self.below(1)],
+                    text: self.bytes(),
+                }
+            }
+            RESPKind::BulkError => RESPType::BulkError(self.bytes()),
+            _ => RESPType::Null,
+        }
+    }
+
+    fn items(&mut self, depth: usize) -> Vec<RESPType> {
+        let len = self.below(self.max_elements);
+        (0..len).map(|_| self.value_at(depth + 1)).collect()
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = self.below(self.max_bytes);
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// Printable ASCII, so that it survives simple-string framing.
+    fn line(&mut self) -> String {
+        let len = self.below(self.max_bytes);
+        (0..len)
+            .map(|_| (b' ' + (self.next_u64() % 95) as u8) as char)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_exact_n;
+
+    #[test]
+    fn streams_parse_back() {
+        let mut gen = Generator::new(7);
+        for _ in 0..50 {
+            let values: Vec<RESPType> = (0..10).map(|_| gen.value()).collect();
+            let mut buf = Vec::new();
+            for value in &values {
+                encode::write_value(&mut buf, value).unwrap();
+            }
+            assert_eq!(parse_exact_n(&buf, 10).unwrap(), values);
+        }
+    }
+
+    #[test]
+    fn is_reproducible() {
+        let a = Generator::new(42).stream(20);
+        let b = Generator::new(42).stream(20);
+        assert_eq!(a, b);
+        assert_ne!(a, Generator::new(43).stream(20));
+    }
+
+    #[test]
+    fn honours_weights_and_limits() {
+        let mut gen = Generator::new(1)
+            .weight(RESPKind::SimpleString, 0)
+            .weight(RESPKind::Error, 0)
+            .weight(RESPKind::Null, 0)
+            .weight(RESPKind::Array, 0)
+            .max_bytes(4);
+        for _ in 0..100 {
+            match gen.value() {
+                RESPType::Integer(_) => {}
+                RESPType::BulkString(b) => assert!(b.len() <= 4),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        let mut flat = Generator::new(1).max_depth(0).resp3();
+        for _ in 0..100 {
+            assert!(!matches!(
+                flat.value().kind(),
+                RESPKind::Array | RESPKind::Map | RESPKind::Set | RESPKind::Push
+            ));
+        }
+        let mut maps = Generator::new(1)
+            .weight(RESPKind::Null, 0)
+            .weight(RESPKind::Array, 0)
+            .weight(RESPKind::Map, 5)
+            .max_depth(1);
+        assert_eq!(maps.value().kind(), RESPKind::Map);
+    }
+
+    #[test]
+    fn resp3_streams_parse_back() {
+        let mut gen = Generator::new(11).resp3();
+        for _ in 0..50 {
+            let values: Vec<RESPType> = (0..10).map(|_| gen.value()).collect();
+            let mut buf = Vec::new();
+            for value in &values {
+                encode::write_value(&mut buf, value).unwrap();
+            }
+            assert_eq!(parse_exact_n(&buf, 10).unwrap(), values);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_kinds_it_cannot_generate() {
+        Generator::new(1).weight(RESPKind::Attributed, 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{RESPType, Result};
+
+/// A transform applied to bulk string payloads, e.g. transparent
+/// decompression of values that a producer compressed before storing them.
+///
+/// Transcoders registered on a [`crate::Parser`] are tried in registration
+/// order for every bulk string read; the first one whose [`matches`]
+/// returns true decodes the payload.
+///
+/// [`matches`]: Transcoder::matches
+pub trait Transcoder: Send {
+    /// Tests whether a payload read from the wire should be decoded.
+    fn matches(&self, payload: &[u8]) -> bool;
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+
+    /// Transforms a payload before it is written, or returns `None` to leave
+    /// it untouched.
+    fn encode(&self, _payload: &[u8]) -> Option<Result<Vec<u8>>> {
+        None
+    }
+}
+
+/// A decode-only transcoder built from a predicate and a decode function.
+pub struct FnTranscoder<P, D> {
+    predicate: P,
+    decode: D,
+}
+
+impl<P, D> FnTranscoder<P, D>
+where
+    P: Fn(&[u8]) -> bool + Send,
+    D: Fn(&[u8]) -> Result<Vec<u8>> + Send,
+{
+    pub fn new(predicate: P, decode: D) -> FnTranscoder<P, D> {
+        FnTranscoder { predicate, decode }
+    }
+}
+
+impl<P, D> Transcoder for FnTranscoder<P, D>
+where
+    P: Fn(&[u8]) -> bool + Send,
+    D: Fn(&[u8]) -> Result<Vec<u8>> + Send,
+{
+    fn matches(&self, payload: &[u8]) -> bool {
+        (self.predicate)(payload)
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        (self.decode)(payload)
+    }
+}
+
+pub(crate) fn decode_payload(
+    transcoders: &[Box<dyn Transcoder>],
+    payload: Vec<u8>,
+) -> Result<Vec<u8>> {
+    match transcoders.iter().find(|t| t.matches(&payload)) {
+        Some(t) => t.decode(&payload),
+        None => Ok(payload),
+    }
+}
+
+/// Applies the first transcoder willing to encode each bulk string payload
+/// in `value`, recursing into aggregates and attributes. Use this before
+/// writing values that should be transformed on the wire.
+pub fn encode_value(transcoders: &[Box<dyn Transcoder>], value: RESPType) -> Result<RESPType> {
+    match value {
+        RESPType::BulkString(buf) => {
+            for t in transcoders {
+                if let Some(encoded) = t.encode(&buf) {
+                    return Ok(RESPType::BulkString(encoded?));
+                }
+            }
+            Ok(RESPType::BulkString(buf))
+        }
+        RESPType::Array(items) => encode_items(transcoders, items).map(RESPType::Array),
+        RESPType::Set(items) => encode_items(transcoders, items).map(RESPType::Set),
+        RESPType::Push(items) => encode_items(transcoders, items).map(RESPType::Push),
+        RESPType::Map(entries) => encode_entries(transcoders, entries).map(RESPType::Map),
+        RESPType::Attributed { attrs, value } => Ok(RESPType::Attributed {
+            attrs: encode_entries(transcoders, attrs)?,
+            value: Box::new(encode_value(transcoders, *value)?),
+        }),
+        other => Ok(other),
+    }
+}
+
+fn encode_items(
+    transcoders: &[Box<dyn Transcoder>],
+    items: Vec<RESPType>,
+) -> Result<Vec<RESPType>> {
+    items
+        .into_iter()
+        .map(|item| encode_value(transcoders, item))
+        .collect()
+}
+
+fn encode_entries(
+    transcoders: &[Box<dyn Transcoder>],
+    entries: Vec<(RESPType, RESPType)>,
+) -> Result<Vec<(RESPType, RESPType)>> {
+    entries
+        .into_iter()
+        .map(|(k, v)| Ok((encode_value(transcoders, k)?, encode_value(transcoders, v)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Parser};
+    use std::io::Read;
+
+    /// Toy "compression": a `~` prefix followed by the payload reversed.
+    struct Reverse;
+
+    impl Transcoder for Reverse {
+        fn matches(&self, payload: &[u8]) -> bool {
+            payload.first() == Some(&b'~')
+        }
+
+        fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+            Ok(payload[1..].iter().rev().cloned().collect())
+        }
+
+        fn encode(&self, payload: &[u8]) -> Option<Result<Vec<u8>>> {
+            let mut out = vec![b'~'];
+            out.extend(payload.iter().rev());
+            Some(Ok(out))
+        }
+    }
+
+    #[test]
+    fn decodes_matching_bulk_strings() {
+        let input = "*2\r\n$4\r\n~cba\r\n$3\r\nxyz\r\n";
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        parser.add_transcoder(Box::new(Reverse));
+        assert_eq!(
+            parser.parse_next().unwrap(),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"abc".to_vec()),
+                RESPType::BulkString(b"xyz".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn propagates_decode_errors() {
+        let input = "$2\r\n!x\r\n";
+        let mut parser = Parser::new(input.as_bytes().bytes());
+        parser.add_transcoder(Box::new(FnTranscoder::new(
+            |p: &[u8]| p.starts_with(b"!"),
+            |_: &[u8]| Err(Error::UnknownError),
+        )));
+        assert!(parser.parse_next().is_err());
+    }
+
+    #[test]
+    fn encodes_nested_values() {
+        let transcoders: Vec<Box<dyn Transcoder>> = vec![Box::new(Reverse)];
+        let value = RESPType::Array(vec![
+            RESPType::BulkString(b"ab".to_vec()),
+            RESPType::Integer(1),
+        ]);
+        assert_eq!(
+            encode_value(&transcoders, value).unwrap(),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"~ba".to_vec()),
+                RESPType::Integer(1),
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trips_maps() {
+        let transcoders: Vec<Box<dyn Transcoder>> = vec![Box::new(Reverse)];
+        let value = RESPType::Map(vec![(
+            RESPType::BulkString(b"key".to_vec()),
+            RESPType::Push(vec![RESPType::BulkString(b"value".to_vec())]),
+        )]);
+        let encoded = encode_value(&transcoders, value.clone()).unwrap();
+        let wire = crate::to_vec(&encoded).unwrap();
+        let mut parser = Parser::buffered(&wire[..]);
+        parser.add_transcoder(Box::new(Reverse));
+        assert_eq!(parser.parse_next().unwrap(), value);
+    }
+}
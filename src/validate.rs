@@ -0,0 +1,150 @@
+use crate::split::{Scanner, Warning};
+use crate::{Error, RESPKind};
+
+/// Outcome of [`validate_report`]: every frame found in the buffer and,
+/// if validation stopped early, the error that stopped it.
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub frames: Vec<FrameReport>,
+    pub error: Option<ValidationError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameReport {
+    pub offset: usize,
+    pub len: usize,
+    pub kind: RESPKind,
+    pub warnings: Vec<Warning>,
+}
+
+/// The first malformed or truncated frame, located by its start offset.
+#[derive(Debug)]
+pub struct ValidationError {
+    pub offset: usize,
+    pub error: Error,
+}
+
+impl ValidationReport {
+    /// True if every byte of the buffer belongs to a well-formed frame.
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// True if the buffer is valid and no frame needed lenient parsing.
+    pub fn is_strict(&self) -> bool {
+        self.is_valid() && self.frames.iter().all(|f| f.warnings.is_empty())
+    }
+}
+
+/// Checks the frames in `buf` without decoding their values, reporting the
+/// offset, length and type of each frame along with any constructs that a
+/// strict parser would reject. Offsets are relative to the start of `buf`.
+pub fn validate_report(buf: &[u8]) -> ValidationReport {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    while offset < buf.len() {
+        let mut scanner = Scanner::new(buf, offset, true);
+        match scanner.skip_frame() {
+            Ok(kind) => {
+                frames.push(FrameReport {
+                    offset,
+                    len: scanner.pos - offset,
+                    kind,
+                    warnings: scanner.warnings.take().unwrap_or_default(),
+                });
+                offset = scanner.pos;
+            }
+            Err(error) => {
+                return ValidationReport {
+                    frames,
+                    error: Some(ValidationError { offset, error }),
+                }
+            }
+        }
+    }
+    ValidationReport {
+        frames,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::WarningKind;
+
+    #[test]
+    fn reports_strict_frames() {
+        let report = validate_report(b"+OK\r\n*2\r\n:1\r\n$1\r\nx\r\n");
+        assert!(report.is_strict());
+        assert_eq!(
+            report.frames,
+            vec![
+                FrameReport {
+                    offset: 0,
+                    len: 5,
+                    kind: RESPKind::SimpleString,
+                    warnings: vec![],
+                },
+                FrameReport {
+                    offset: 5,
+                    len: 15,
+                    kind: RESPKind::Array,
+                    warnings: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_lenient_constructs() {
+        let report = validate_report(b":+07\r\n+a\rb\r\n$1\r\nxy\r\n:1\n$-1\r\n");
+        assert!(report.is_valid());
+        assert!(!report.is_strict());
+        let kinds: Vec<Vec<WarningKind>> = report
+            .frames
+            .iter()
+            .map(|f| f.warnings.iter().map(|w| w.kind).collect())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                vec![
+                    WarningKind::IntegerPlusSign,
+                    WarningKind::IntegerLeadingZeros
+                ],
+                vec![WarningKind::StrayCarriageReturn],
+                vec![WarningKind::TrailingBytes],
+                vec![WarningKind::BareLineFeed],
+                vec![WarningKind::Resp2Null],
+            ]
+        );
+        assert_eq!(report.frames[1].warnings[0].offset, 8);
+        assert_eq!(report.frames[4].warnings[0].offset, 23);
+    }
+
+    #[test]
+    fn stops_at_first_error() {
+        let report = validate_report(b":1\r\n:x\r\n:2\r\n");
+        assert_eq!(report.frames.len(), 1);
+        let error = report.error.unwrap();
+        assert_eq!(error.offset, 4);
+        match error.error {
+            Error::MalformedInteger { offset: 5, .. } => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_truncated_frame() {
+        let report = validate_report(b"*2\r\n:1\r\n");
+        assert!(report.frames.is_empty());
+        match report.error {
+            Some(ValidationError {
+                offset: 0,
+                error: Error::Incomplete { needed: None },
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,705 @@
+use crate::escape::Escaped;
+use crate::extension::Extension;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A RESP2 or RESP3 value.
+///
+/// Equality and hashing treat all NaN doubles as equal to each other, and
+/// `0.0` as equal to `-0.0`, so values can be used as map keys. New variants
+/// may be added as the protocol grows; prefer the `as_*` accessors to
+/// matching on every variant.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum RESPType {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    BulkString(Vec<u8>),
+    Null,
+    Array(Vec<RESPType>),
+    /// Key/value pairs in the order the server sent them.
+    Map(Vec<(RESPType, RESPType)>),
+    Set(Vec<RESPType>),
+    /// Out-of-band data such as pub/sub messages, sent outside the normal
+    /// request/reply flow.
+    Push(Vec<RESPType>),
+    Double(f64),
+    Boolean(bool),
+    /// An integer of arbitrary size, as its decimal digits.
+    BigNumber(String),
+    /// Text along with its three-byte format, such as `txt` or `mkd`.
+    Verbatim {
+        format: [u8; 3],
+        text: Vec<u8>,
+    },
+    /// An error whose message is length-prefixed and may be binary.
+    BulkError(Vec<u8>),
+    /// A value preceded by an attribute frame carrying metadata about it.
+    Attributed {
+        attrs: Vec<(RESPType, RESPType)>,
+        value: Box<RESPType>,
+    },
+    Extension(Extension),
+}
+
+/// The type of a [`RESPType`] value, without its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RESPKind {
+    SimpleString,
+    Error,
+    Integer,
+    BulkString,
+    Null,
+    Array,
+    Map,
+    Set,
+    Push,
+    Double,
+    Boolean,
+    BigNumber,
+    Verbatim,
+    BulkError,
+    Attributed,
+    Extension,
+}
+
+impl RESPKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RESPKind::SimpleString => "simple string",
+            RESPKind::Error => "error",
+            RESPKind::Integer => "integer",
+            RESPKind::BulkString => "bulk string",
+            RESPKind::Null => "null",
+            RESPKind::Array => "array",
+            RESPKind::Map => "map",
+            RESPKind::Set => "set",
+            RESPKind::Push => "push",
+            RESPKind::Double => "double",
+            RESPKind::Boolean => "boolean",
+            RESPKind::BigNumber => "big number",
+            RESPKind::Verbatim => "verbatim string",
+            RESPKind::BulkError => "bulk error",
+            RESPKind::Attributed => "attributed value",
+            RESPKind::Extension => "extension",
+        }
+    }
+}
+
+impl fmt::Display for RESPKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl RESPType {
+    pub fn kind(&self) -> RESPKind {
+        match self {
+            RESPType::SimpleString(_) => RESPKind::SimpleString,
+            RESPType::Error(_) => RESPKind::Error,
+            RESPType::Integer(_) => RESPKind::Integer,
+            RESPType::BulkString(_) => RESPKind::BulkString,
+            RESPType::Null => RESPKind::Null,
+            RESPType::Array(_) => RESPKind::Array,
+            RESPType::Map(_) => RESPKind::Map,
+            RESPType::Set(_) => RESPKind::Set,
+            RESPType::Push(_) => RESPKind::Push,
+            RESPType::Double(_) => RESPKind::Double,
+            RESPType::Boolean(_) => RESPKind::Boolean,
+            RESPType::BigNumber(_) => RESPKind::BigNumber,
+            RESPType::Verbatim { .. } => RESPKind::Verbatim,
+            RESPType::BulkError(_) => RESPKind::BulkError,
+            RESPType::Attributed { .. } => RESPKind::Attributed,
+            RESPType::Extension(_) => RESPKind::Extension,
+        }
+    }
+
+    /// Estimates the number of heap bytes owned by this value, based on the
+    /// allocated capacity of its strings and vectors. The inline size of the
+    /// value itself is not included.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => s.capacity(),
+            RESPType::BulkString(buf)
+            | RESPType::BulkError(buf)
+            | RESPType::Verbatim { text: buf, .. } => buf.capacity(),
+            RESPType::Extension(ext) => ext.payload.capacity(),
+            RESPType::Integer(_) | RESPType::Null | RESPType::Double(_) | RESPType::Boolean(_) => 0,
+            RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => {
+                items.capacity() * std::mem::size_of::<RESPType>()
+                    + items.iter().map(RESPType::heap_size).sum::<usize>()
+            }
+            RESPType::Map(entries) => entries_heap_size(entries),
+            RESPType::Attributed { attrs, value } => {
+                entries_heap_size(attrs) + std::mem::size_of::<RESPType>() + value.heap_size()
+            }
+        }
+    }
+
+    /// Returns a canonical form of this value for comparing replies that
+    /// are equivalent but encoded differently:
+    ///
+    /// - simple strings become bulk strings with the same bytes, since
+    ///   servers differ in which of the two they use for status replies;
+    /// - verbatim strings become bulk strings holding their text, which is
+    ///   what RESP2 servers send instead;
+    /// - doubles become bulk strings holding their shortest decimal form,
+    ///   as RESP2 servers send them, with `-0` written as `0` and every NaN
+    ///   as `nan`;
+    /// - attributes are dropped, as they are metadata rather than reply
+    ///   content;
+    /// - aggregate elements are normalized recursively;
+    /// - set elements, and map entries by key, are sorted by their encoding,
+    ///   since their order carries no meaning.
+    ///
+    /// The RESP2 and RESP3 null encodings need no rule of their own, since
+    /// both parse to [`RESPType::Null`].
+    pub fn normalized(&self) -> RESPType {
+        match self {
+            RESPType::SimpleString(s) => RESPType::BulkString(s.as_bytes().to_vec()),
+            RESPType::Double(d) if d.is_nan() => RESPType::BulkString(b"nan".to_vec()),
+            RESPType::Double(d) if *d == 0.0 => RESPType::BulkString(b"0".to_vec()),
+            RESPType::Double(d) => RESPType::BulkString(d.to_string().into_bytes()),
+            RESPType::Verbatim { text, .. } => RESPType::BulkString(text.clone()),
+            RESPType::Array(items) => {
+                RESPType::Array(items.iter().map(RESPType::normalized).collect())
+            }
+            RESPType::Push(items) => {
+                RESPType::Push(items.iter().map(RESPType::normalized).collect())
+            }
+            RESPType::Set(items) => {
+                let mut items: Vec<RESPType> = items.iter().map(RESPType::normalized).collect();
+                items.sort_by_cached_key(crate::encode::to_vec_unchecked);
+                RESPType::Set(items)
+            }
+            RESPType::Attributed { value, .. } => value.normalized(),
+            RESPType::Map(entries) => {
+                let mut entries: Vec<(RESPType, RESPType)> = entries
+                    .iter()
+                    .map(|(k, v)| (k.normalized(), v.normalized()))
+                    .collect();
+                entries.sort_by_cached_key(|(k, _)| crate::encode::to_vec_unchecked(k));
+                RESPType::Map(entries)
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Converts the value into the form a RESP2 client expects, the way
+    /// Redis replies to a connection that has not sent `HELLO 3`:
+    ///
+    /// - maps become flat arrays of keys and values;
+    /// - sets and pushes become arrays;
+    /// - doubles, big numbers and verbatim strings become bulk strings;
+    /// - booleans become the integers 1 and 0;
+    /// - bulk errors become simple errors, with line breaks replaced by
+    ///   spaces;
+    /// - attributes are dropped.
+    ///
+    /// Extension frames are left unchanged.
+    pub fn to_resp2(&self) -> RESPType {
+        match self {
+            RESPType::Map(entries) => RESPType::Array(
+                entries
+                    .iter()
+                    .flat_map(|(k, v)| vec![k.to_resp2(), v.to_resp2()])
+                    .collect(),
+            ),
+            RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => {
+                RESPType::Array(items.iter().map(RESPType::to_resp2).collect())
+            }
+            RESPType::Double(d) if d.is_nan() => RESPType::BulkString(b"nan".to_vec()),
+            RESPType::Double(d) => RESPType::BulkString(d.to_string().into_bytes()),
+            RESPType::BigNumber(n) => RESPType::BulkString(n.as_bytes().to_vec()),
+            RESPType::Verbatim { text, .. } => RESPType::BulkString(text.clone()),
+            RESPType::Boolean(b) => RESPType::Integer(*b as i64),
+            RESPType::BulkError(msg) => {
+                let msg = String::from_utf8_lossy(msg).replace(['\r', '\n'], " ");
+                RESPType::Error(msg)
+            }
+            RESPType::Attributed { value, .. } => value.to_resp2(),
+            other => other.clone(),
+        }
+    }
+
+    pub fn is_push(&self) -> bool {
+        matches!(self.without_attributes(), RESPType::Push(_))
+    }
+
+    /// The value itself, with any attributes attached to it skipped.
+    pub fn without_attributes(&self) -> &RESPType {
+        match self {
+            RESPType::Attributed { value, .. } => value.without_attributes(),
+            other => other,
+        }
+    }
+
+    // The accessors below look through attributes, like `is_push`.
+
+    /// The text of a simple string, or of a bulk string or verbatim string
+    /// that is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self.without_attributes() {
+            RESPType::SimpleString(s) => Some(s),
+            RESPType::BulkString(b) | RESPType::Verbatim { text: b, .. } => {
+                std::str::from_utf8(b).ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self.without_attributes() {
+            RESPType::SimpleString(s) => Some(s.as_bytes()),
+            RESPType::BulkString(b) | RESPType::Verbatim { text: b, .. } => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self.without_attributes() {
+            RESPType::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self.without_attributes() {
+            RESPType::Double(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.without_attributes() {
+            RESPType::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The elements of an array, set or push frame.
+    pub fn as_array(&self) -> Option<&[RESPType]> {
+        match self.without_attributes() {
+            RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(RESPType, RESPType)]> {
+        match self.without_attributes() {
+            RESPType::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.without_attributes(), RESPType::Null)
+    }
+
+    /// Whether this is a simple or bulk error.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.without_attributes(),
+            RESPType::Error(_) | RESPType::BulkError(_)
+        )
+    }
+}
+
+impl PartialEq for RESPType {
+    fn eq(&self, other: &RESPType) -> bool {
+        match (self, other) {
+            (RESPType::SimpleString(l), RESPType::SimpleString(r))
+            | (RESPType::Error(l), RESPType::Error(r))
+            | (RESPType::BigNumber(l), RESPType::BigNumber(r)) => l == r,
+            (RESPType::Integer(l), RESPType::Integer(r)) => l == r,
+            (RESPType::BulkString(l), RESPType::BulkString(r))
+            | (RESPType::BulkError(l), RESPType::BulkError(r)) => l == r,
+            (RESPType::Null, RESPType::Null) => true,
+            (RESPType::Array(l), RESPType::Array(r))
+            | (RESPType::Set(l), RESPType::Set(r))
+            | (RESPType::Push(l), RESPType::Push(r)) => l == r,
+            (RESPType::Map(l), RESPType::Map(r)) => l == r,
+            (RESPType::Double(l), RESPType::Double(r)) => double_bits(*l) == double_bits(*r),
+            (RESPType::Boolean(l), RESPType::Boolean(r)) => l == r,
+            (
+                RESPType::Verbatim {
+                    format: lf,
+                    text: lt,
+                },
+                RESPType::Verbatim {
+                    format: rf,
+                    text: rt,
+                },
+            ) => lf == rf && lt == rt,
+            (
+                RESPType::Attributed {
+                    attrs: la,
+                    value: lv,
+                },
+                RESPType::Attributed {
+                    attrs: ra,
+                    value: rv,
+                },
+            ) => la == ra && lv == rv,
+            (RESPType::Extension(l), RESPType::Extension(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RESPType {}
+
+impl Hash for RESPType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => {
+                s.hash(state)
+            }
+            RESPType::Integer(i) => i.hash(state),
+            RESPType::BulkString(b) | RESPType::BulkError(b) => b.hash(state),
+            RESPType::Null => {}
+            RESPType::Array(items) | RESPType::Set(items) | RESPType::Push(items) => {
+                items.hash(state)
+            }
+            RESPType::Map(entries) => entries.hash(state),
+            RESPType::Double(d) => double_bits(*d).hash(state),
+            RESPType::Boolean(b) => b.hash(state),
+            RESPType::Verbatim { format, text } => {
+                format.hash(state);
+                text.hash(state);
+            }
+            RESPType::Attributed { attrs, value } => {
+                attrs.hash(state);
+                value.hash(state);
+            }
+            RESPType::Extension(ext) => ext.hash(state),
+        }
+    }
+}
+
+/// The bits a double is compared and hashed by, with every NaN and both
+/// zeroes collapsed to one representation.
+fn double_bits(d: f64) -> u64 {
+    if d.is_nan() {
+        f64::NAN.to_bits()
+    } else if d == 0.0 {
+        0
+    } else {
+        d.to_bits()
+    }
+}
+
+/// Renders a value the way `redis-cli` does: `(integer) 5`, quoted bulk
+/// strings, `(nil)`, and numbered elements with nested aggregates indented
+/// under their index.
+impl fmt::Display for RESPType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tty(f, self, 0)
+    }
+}
+
+fn write_tty(f: &mut fmt::Formatter, value: &RESPType, indent: usize) -> fmt::Result {
+    match value {
+        RESPType::SimpleString(s) => f.write_str(s),
+        RESPType::Error(s) => f.write_fmt(format_args!("(error) {}", s)),
+        RESPType::BulkError(b) => {
+            f.write_fmt(format_args!("(error) {}", String::from_utf8_lossy(b)))
+        }
+        RESPType::Integer(i) => f.write_fmt(format_args!("(integer) {}", i)),
+        RESPType::BulkString(b) => f.write_fmt(format_args!("{}", Escaped::new(b))),
+        RESPType::Null => f.write_str("(nil)"),
+        RESPType::Double(d) => f.write_fmt(format_args!("(double) {}", d)),
+        RESPType::Boolean(b) => f.write_fmt(format_args!("({})", b)),
+        RESPType::BigNumber(n) => f.write_fmt(format_args!("(big number) {}", n)),
+        RESPType::Verbatim { text, .. } => f.write_str(&String::from_utf8_lossy(text)),
+        RESPType::Extension(ext) => f.write_fmt(format_args!(
+            "(extension {}) {}",
+            ext.tag as char,
+            Escaped::new(&ext.payload)
+        )),
+        RESPType::Array(items) | RESPType::Push(items) => write_items(f, items, ')', indent),
+        RESPType::Set(items) => write_items(f, items, '~', indent),
+        RESPType::Map(entries) => write_entries(f, entries, '#', indent),
+        RESPType::Attributed { attrs, value } => {
+            write_entries(f, attrs, '|', indent)?;
+            f.write_str("\n")?;
+            f.write_fmt(format_args!("{:1$}", "", indent))?;
+            write_tty(f, value, indent)
+        }
+    }
+}
+
+/// Writes the prefix of the `i`th of `len` elements, padding the index so
+/// that the markers line up, and returns the column the element starts at.
+fn write_index(
+    f: &mut fmt::Formatter,
+    i: usize,
+    len: usize,
+    marker: char,
+    indent: usize,
+) -> Result<usize, fmt::Error> {
+    let width = len.to_string().len();
+    if i > 0 {
+        f.write_fmt(format_args!("\n{:1$}", "", indent))?;
+    }
+    f.write_fmt(format_args!("{:>2$}{} ", i + 1, marker, width))?;
+    Ok(indent + width + 2)
+}
+
+fn write_items(
+    f: &mut fmt::Formatter,
+    items: &[RESPType],
+    marker: char,
+    indent: usize,
+) -> fmt::Result {
+    if items.is_empty() {
+        return f.write_str(if marker == '~' {
+            "(empty set)"
+        } else {
+            "(empty array)"
+        });
+    }
+    for (i, item) in items.iter().enumerate() {
+        let indent = write_index(f, i, items.len(), marker, indent)?;
+        write_tty(f, item, indent)?;
+    }
+    Ok(())
+}
+
+fn write_entries(
+    f: &mut fmt::Formatter,
+    entries: &[(RESPType, RESPType)],
+    marker: char,
+    indent: usize,
+) -> fmt::Result {
+    if entries.is_empty() {
+        return f.write_str("(empty hash)");
+    }
+    for (i, (k, v)) in entries.iter().enumerate() {
+        let indent = write_index(f, i, entries.len(), marker, indent)?;
+        write_tty(f, k, indent)?;
+        f.write_str(" => ")?;
+        write_tty(f, v, indent)?;
+    }
+    Ok(())
+}
+
+fn entries_heap_size(entries: &Vec<(RESPType, RESPType)>) -> usize {
+    entries.capacity() * std::mem::size_of::<(RESPType, RESPType)>()
+        + entries
+            .iter()
+            .map(|(k, v)| k.heap_size() + v.heap_size())
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heap_size_accounts_for_capacity() {
+        assert_eq!(RESPType::Integer(1).heap_size(), 0);
+        assert_eq!(RESPType::BulkString(Vec::with_capacity(64)).heap_size(), 64);
+
+        let mut items = Vec::with_capacity(4);
+        items.push(RESPType::SimpleString(String::with_capacity(10)));
+        items.push(RESPType::Array(Vec::with_capacity(2)));
+        let want = 4 * std::mem::size_of::<RESPType>() + 10 + 2 * std::mem::size_of::<RESPType>();
+        assert_eq!(RESPType::Array(items).heap_size(), want);
+    }
+
+    #[test]
+    fn normalizes_equivalent_encodings() {
+        let status = RESPType::Array(vec![
+            RESPType::SimpleString("OK".to_string()),
+            RESPType::Error("ERR x".to_string()),
+        ]);
+        let bulk = RESPType::Array(vec![
+            RESPType::BulkString(b"OK".to_vec()),
+            RESPType::Error("ERR x".to_string()),
+        ]);
+        assert_ne!(status, bulk);
+        assert_eq!(status.normalized(), bulk.normalized());
+        assert_eq!(bulk.normalized(), bulk);
+    }
+
+    #[test]
+    fn normalizes_doubles() {
+        assert_eq!(
+            RESPType::Double(-0.0).normalized(),
+            RESPType::BulkString(b"0".to_vec())
+        );
+        assert_eq!(
+            RESPType::Double(f64::from_bits(f64::NAN.to_bits() | 1)).normalized(),
+            RESPType::BulkString(b"nan".to_vec())
+        );
+        assert_eq!(
+            RESPType::Double(1.5).normalized(),
+            RESPType::BulkString(b"1.5".to_vec()).normalized()
+        );
+        assert_eq!(
+            RESPType::Double(f64::NEG_INFINITY).normalized(),
+            RESPType::BulkString(b"-inf".to_vec())
+        );
+    }
+
+    #[test]
+    fn normalizes_unordered_aggregates() {
+        let a = RESPType::Map(vec![
+            (
+                RESPType::BulkString(b"b".to_vec()),
+                RESPType::Set(vec![RESPType::Integer(2), RESPType::Integer(1)]),
+            ),
+            (RESPType::SimpleString("a".to_string()), RESPType::Null),
+        ]);
+        let b = RESPType::Map(vec![
+            (RESPType::BulkString(b"a".to_vec()), RESPType::Null),
+            (
+                RESPType::BulkString(b"b".to_vec()),
+                RESPType::Set(vec![RESPType::Integer(1), RESPType::Integer(2)]),
+            ),
+        ]);
+        assert_eq!(a.normalized(), b.normalized());
+        assert_eq!(b.normalized(), b);
+    }
+
+    #[test]
+    fn recognizes_push_frames() {
+        let push = RESPType::Push(vec![RESPType::BulkString(b"invalidate".to_vec())]);
+        assert!(push.is_push());
+        assert!(!RESPType::Array(vec![]).is_push());
+        let attributed = RESPType::Attributed {
+            attrs: vec![],
+            value: Box::new(push),
+        };
+        assert!(attributed.is_push());
+    }
+
+    #[test]
+    fn downgrades_to_resp2() {
+        let value = RESPType::Attributed {
+            attrs: vec![],
+            value: Box::new(RESPType::Map(vec![
+                (
+                    RESPType::SimpleString("a".to_string()),
+                    RESPType::Set(vec![RESPType::Double(1.5), RESPType::Boolean(true)]),
+                ),
+                (
+                    RESPType::BigNumber("123".to_string()),
+                    RESPType::Verbatim {
+                        format: *b"txt",
+                        text: b"hi".to_vec(),
+                    },
+                ),
+            ])),
+        };
+        assert_eq!(
+            value.to_resp2(),
+            RESPType::Array(vec![
+                RESPType::SimpleString("a".to_string()),
+                RESPType::Array(vec![
+                    RESPType::BulkString(b"1.5".to_vec()),
+                    RESPType::Integer(1),
+                ]),
+                RESPType::BulkString(b"123".to_vec()),
+                RESPType::BulkString(b"hi".to_vec()),
+            ])
+        );
+        assert_eq!(
+            RESPType::BulkError(b"ERR a\r\nb".to_vec()).to_resp2(),
+            RESPType::Error("ERR a  b".to_string())
+        );
+        assert_eq!(
+            RESPType::Double(f64::NAN).to_resp2(),
+            RESPType::BulkString(b"nan".to_vec())
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let bulk = RESPType::BulkString(b"hi".to_vec());
+        assert_eq!(bulk.as_str(), Some("hi"));
+        assert_eq!(bulk.as_bytes(), Some(&b"hi"[..]));
+        assert_eq!(bulk.as_int(), None);
+        assert_eq!(RESPType::BulkString(vec![0xff]).as_str(), None);
+        assert_eq!(RESPType::Integer(3).as_int(), Some(3));
+        assert_eq!(RESPType::Boolean(true).as_bool(), Some(true));
+        assert_eq!(RESPType::Double(0.5).as_float(), Some(0.5));
+
+        let set = RESPType::Set(vec![RESPType::Null]);
+        assert_eq!(set.as_array(), Some(&[RESPType::Null][..]));
+        assert!(set.as_array().unwrap()[0].is_null());
+        assert_eq!(RESPType::Map(vec![]).as_map().map(<[_]>::len), Some(0));
+
+        assert!(RESPType::BulkError(b"ERR x".to_vec()).is_error());
+        assert!(!RESPType::SimpleString("ERR".to_string()).is_error());
+
+        let attributed = RESPType::Attributed {
+            attrs: vec![],
+            value: Box::new(RESPType::Integer(1)),
+        };
+        assert_eq!(attributed.as_int(), Some(1));
+    }
+
+    #[test]
+    fn displays_like_redis_cli() {
+        assert_eq!(RESPType::Integer(5).to_string(), "(integer) 5");
+        assert_eq!(
+            RESPType::BulkString(b"a\"b\n".to_vec()).to_string(),
+            "\"a\\\"b\\n\""
+        );
+        assert_eq!(RESPType::Null.to_string(), "(nil)");
+        assert_eq!(RESPType::Array(vec![]).to_string(), "(empty array)");
+
+        let items: Vec<RESPType> = (0..10).map(RESPType::Integer).collect();
+        let value = RESPType::Array(vec![
+            RESPType::SimpleString("OK".to_string()),
+            RESPType::Array(vec![
+                RESPType::BulkString(b"a".to_vec()),
+                RESPType::Error("ERR x".to_string()),
+            ]),
+            RESPType::Map(vec![(
+                RESPType::BulkString(b"k".to_vec()),
+                RESPType::Set(vec![RESPType::Boolean(true)]),
+            )]),
+            RESPType::Array(items),
+        ]);
+        let want = "\
+1) OK
+2) 1) \"a\"
+   2) (error) ERR x
+3) 1# \"k\" => 1~ (true)
+4)  1) (integer) 0
+    2) (integer) 1
+    3) (integer) 2
+    4) (integer) 3
+    5) (integer) 4
+    6) (integer) 5
+    7) (integer) 6
+    8) (integer) 7
+    9) (integer) 8
+   10) (integer) 9";
+        assert_eq!(value.to_string(), want);
+    }
+
+    #[test]
+    fn usable_as_hash_key() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        assert!(set.insert(RESPType::Double(f64::NAN)));
+        assert!(!set.insert(RESPType::Double(-f64::NAN)));
+        assert!(set.insert(RESPType::Double(0.0)));
+        assert!(!set.insert(RESPType::Double(-0.0)));
+        assert!(set.insert(RESPType::BulkString(b"a".to_vec())));
+        assert!(set.insert(RESPType::SimpleString("a".to_string())));
+        assert!(set.insert(RESPType::Array(vec![RESPType::Integer(1)])));
+        assert!(!set.insert(RESPType::Array(vec![RESPType::Integer(1)])));
+        assert_eq!(set.len(), 5);
+        assert_eq!(RESPType::Double(1.5), RESPType::Double(1.5));
+        assert_ne!(RESPType::Double(1.5), RESPType::Double(2.5));
+    }
+}